@@ -0,0 +1,215 @@
+use crate::cli::ShellKind;
+use crate::environment::Environment;
+use crate::errors::BeError;
+use crate::output;
+use crate::run;
+use std::env;
+
+/// Marca que el script ya se aplico, para que activar dos veces no pierda los valores viejos.
+const ACTIVE_VAR: &str = "_BE_ACTIVE";
+/// Prefijo de las copias de los valores anteriores (ej. `_BE_OLD_PATH`).
+const OLD_PREFIX: &str = "_BE_OLD_";
+/// Code page de la consola mientras corre el script de cmd.
+const CODE_PAGE_VAR: &str = "_BE_CODEPAGE";
+
+/// `be env --shell <shell>`: imprime el script que activa el entorno en la terminal actual.
+/// Si falta una herramienta se instala como en `be run`; sus mensajes van a stderr para que
+/// `eval`/`Invoke-Expression` solo reciban el script.
+pub fn print_script(shell: Option<ShellKind>) -> Result<(), BeError> {
+    output::set_script(true);
+    let environment = Environment::load()?;
+    let shell = shell.unwrap_or_else(detect_shell);
+    print!("{}", script(shell, &run::child_env(&environment)?));
+    Ok(())
+}
+
+/// Sin `--shell`: PowerShell en Windows; fuera, fish si es la shell del usuario y si no bash.
+fn detect_shell() -> ShellKind {
+    if cfg!(windows) {
+        return ShellKind::Pwsh;
+    }
+    match env::var("SHELL") {
+        Ok(shell) if shell.ends_with("fish") => ShellKind::Fish,
+        _ => ShellKind::Bash,
+    }
+}
+
+/// Script que guarda los valores actuales, aplica `vars` y define `deactivate`.
+pub fn script(shell: ShellKind, vars: &[(String, String)]) -> String {
+    let mut out = String::new();
+    match shell {
+        ShellKind::Bash => {
+            out.push_str("# Brisas: eval \"$(be env --shell bash)\"\n");
+            out.push_str("deactivate () {\n");
+            for (key, _) in vars {
+                let old = format!("{}{}", OLD_PREFIX, key);
+                out.push_str(&format!(
+                    "    if [ -n \"${{{old}+x}}\" ]; then {key}=\"${old}\"; export {key}; \
+                     unset {old}; else unset {key}; fi\n"
+                ));
+            }
+            out.push_str(&format!("    unset {}\n", ACTIVE_VAR));
+            out.push_str("    unset -f deactivate\n");
+            out.push_str("}\n");
+            for (key, value) in vars {
+                let old = format!("{}{}", OLD_PREFIX, key);
+                out.push_str(&format!(
+                    "if [ -z \"${{{ACTIVE_VAR}-}}\" ] && [ -n \"${{{key}+x}}\" ]; then \
+                     {old}=\"${key}\"; fi\n"
+                ));
+                out.push_str(&format!("export {}={}\n", key, quote_sh(value)));
+            }
+            out.push_str(&format!("export {}=1\n", ACTIVE_VAR));
+        }
+        ShellKind::Fish => {
+            out.push_str("# Brisas: be env --shell fish | source\n");
+            out.push_str("function deactivate\n");
+            for (key, _) in vars {
+                let old = format!("{}{}", OLD_PREFIX, key);
+                out.push_str(&format!(
+                    "    if set -q {old}; set -gx {key} ${old}; set -e {old}; else; set -e {key}; end\n"
+                ));
+            }
+            out.push_str(&format!("    set -e {}\n", ACTIVE_VAR));
+            out.push_str("    functions -e deactivate\n");
+            out.push_str("end\n");
+            for (key, value) in vars {
+                let old = format!("{}{}", OLD_PREFIX, key);
+                out.push_str(&format!(
+                    "if not set -q {ACTIVE_VAR}; and set -q {key}; set -g {old} ${key}; end\n"
+                ));
+                // PATH is a list in fish
+                let values: Vec<String> = if key == "PATH" {
+                    env::split_paths(value)
+                        .map(|p| quote_sh(&p.to_string_lossy()))
+                        .collect()
+                } else {
+                    vec![quote_sh(value)]
+                };
+                out.push_str(&format!("set -gx {} {}\n", key, values.join(" ")));
+            }
+            out.push_str(&format!("set -gx {} 1\n", ACTIVE_VAR));
+        }
+        ShellKind::Pwsh => {
+            out.push_str("# Brisas: be env --shell pwsh | Out-String | Invoke-Expression\n");
+            out.push_str("if (-not $env:_BE_ACTIVE) {\n");
+            out.push_str("    $global:_BE_OLD = @{}\n");
+            for (key, _) in vars {
+                out.push_str(&format!(
+                    "    $global:_BE_OLD['{key}'] = [Environment]::GetEnvironmentVariable('{key}')\n"
+                ));
+            }
+            out.push_str("}\n");
+            for (key, value) in vars {
+                out.push_str(&format!("$env:{} = {}\n", key, quote_pwsh(value)));
+            }
+            out.push_str(&format!("$env:{} = '1'\n", ACTIVE_VAR));
+            out.push_str("function global:deactivate {\n");
+            out.push_str("    foreach ($name in $global:_BE_OLD.Keys) {\n");
+            out.push_str(
+                "        [Environment]::SetEnvironmentVariable($name, $global:_BE_OLD[$name])\n",
+            );
+            out.push_str("    }\n");
+            out.push_str(&format!("    Remove-Item Env:\\{}\n", ACTIVE_VAR));
+            out.push_str("    Remove-Variable -Name _BE_OLD -Scope Global\n");
+            out.push_str("    Remove-Item Function:\\deactivate\n");
+            out.push_str("}\n");
+        }
+        ShellKind::Cmd => {
+            // cmd has no functions: `deactivate` is a doskey macro and the old values are
+            // expanded while the batch file runs (undefined variables expand to nothing)
+            out.push_str("@echo off\n");
+            out.push_str("REM Brisas: be env --shell cmd > be-env.cmd && call be-env.cmd\n");
+            // The script is UTF-8 but cmd reads batch files in the OEM code page: switch
+            // to 65001 so paths like C:\Users\José survive, and restore it at the end
+            out.push_str(&format!(
+                "for /f \"tokens=2 delims=:.\" %%c in ('chcp') do set \"{CODE_PAGE_VAR}=%%c\"\n"
+            ));
+            out.push_str("chcp 65001 >nul\n");
+            out.push_str(&format!("if defined {} goto :apply\n", ACTIVE_VAR));
+            for (key, _) in vars {
+                out.push_str(&format!("set \"{OLD_PREFIX}{key}=%{key}%\"\n"));
+            }
+            out.push_str(":apply\n");
+            for (key, value) in vars {
+                out.push_str(&format!("set \"{}={}\"\n", key, value.replace('%', "%%")));
+            }
+            out.push_str(&format!("set \"{}=1\"\n", ACTIVE_VAR));
+            let mut restore: Vec<String> = vars
+                .iter()
+                .map(|(key, _)| {
+                    format!("set \"{key}=%{OLD_PREFIX}{key}%\" $T set \"{OLD_PREFIX}{key}=\"")
+                })
+                .collect();
+            restore.push(format!("set \"{}=\"", ACTIVE_VAR));
+            restore.push("doskey deactivate=".to_string());
+            out.push_str(&format!("doskey deactivate={}\n", restore.join(" $T ")));
+            out.push_str(&format!("chcp %{CODE_PAGE_VAR}% >nul\n"));
+            out.push_str(&format!("set \"{CODE_PAGE_VAR}=\"\n"));
+            // Label lookup (`goto :apply`) is unreliable in batch files with bare LF endings
+            return out.replace('\n', "\r\n");
+        }
+    }
+    out
+}
+
+fn quote_sh(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn quote_pwsh(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> Vec<(String, String)> {
+        vec![
+            ("PATH".to_string(), "/be/node/bin:/usr/bin".to_string()),
+            ("CC".to_string(), "/be/it's/gcc".to_string()),
+        ]
+    }
+
+    #[test]
+    fn test_bash_script_saves_and_restores() {
+        let script = script(ShellKind::Bash, &vars());
+        assert!(script.contains("export CC='/be/it'\\''s/gcc'\n"));
+        assert!(script.contains("_BE_OLD_PATH=\"$PATH\""));
+        assert!(script.contains("deactivate () {"));
+        assert!(script.contains("else unset CC; fi"));
+    }
+
+    #[test]
+    fn test_pwsh_and_cmd_scripts_define_deactivate() {
+        let pwsh = script(ShellKind::Pwsh, &vars());
+        assert!(pwsh.contains("$env:CC = '/be/it''s/gcc'"));
+        assert!(pwsh.contains("function global:deactivate {"));
+
+        let cmd = script(ShellKind::Cmd, &vars());
+        assert!(cmd.contains("set \"_BE_OLD_CC=%CC%\""));
+        assert!(cmd.contains("doskey deactivate=set \"PATH=%_BE_OLD_PATH%\""));
+    }
+
+    #[test]
+    fn test_cmd_script_uses_crlf_and_utf8_code_page() {
+        let vars = vec![(
+            "PATH".to_string(),
+            "C:\\Users\\José\\AppData\\Local\\node".to_string(),
+        )];
+        let cmd = script(ShellKind::Cmd, &vars);
+        assert!(cmd.ends_with("\r\n"));
+        assert_eq!(cmd.matches('\n').count(), cmd.matches("\r\n").count());
+        assert!(cmd.contains(":apply\r\n"));
+
+        let lines: Vec<&str> = cmd.lines().collect();
+        let utf8 = lines.iter().position(|l| *l == "chcp 65001 >nul").unwrap();
+        let path = lines.iter().position(|l| l.contains("José")).unwrap();
+        let restore = lines
+            .iter()
+            .position(|l| *l == "chcp %_BE_CODEPAGE% >nul")
+            .unwrap();
+        assert!(utf8 < path && path < restore);
+    }
+}
@@ -1,12 +1,19 @@
 use crate::download;
 use crate::errors::BeError;
+use crate::http;
 use crate::manifest::{Manifest, Tool};
 use inquire::{Confirm, Select, Text};
 use log::info;
+use serde::Serialize;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+/// Carpeta temporal donde el asistente descarga archivos para calcular su hash.
+pub fn hash_calc_dir() -> PathBuf {
+    std::env::temp_dir().join("Brisas_Hash_Calc")
+}
+
 pub fn generate_manifest() -> Result<(), BeError> {
     println!("Asistente de Generacion de Manifiesto (Admin)");
     println!("Este asistente te ayudara a gestionar el archivo 'tools.json'.");
@@ -94,7 +101,7 @@ fn edit_tools(mut manifest: Manifest) -> Result<Manifest, BeError> {
             // Hashing
             println!("Calculando Hash SHA256 (Descargando temporalmente)...");
 
-            let temp_dir = std::env::temp_dir().join("Brisas_Hash_Calc");
+            let temp_dir = hash_calc_dir();
             if !temp_dir.exists() {
                 fs::create_dir_all(&temp_dir)?;
             }
@@ -140,11 +147,10 @@ fn edit_tools(mut manifest: Manifest) -> Result<Manifest, BeError> {
             let _ = fs::remove_file(&temp_file);
 
             new_tools.push(Tool {
-                name: tool.name.clone(),
                 version: new_version,
                 url: new_url,
-                check_file: tool.check_file.clone(),
                 sha256: Some(hash),
+                ..tool.clone()
             });
         }
     }
@@ -153,26 +159,80 @@ fn edit_tools(mut manifest: Manifest) -> Result<Manifest, BeError> {
     Ok(manifest)
 }
 
+/// Resultado de `be manifest-gen --output json`: el manifiesto y el estado de cada URL.
+#[derive(Debug, Serialize)]
+pub struct ManifestReport {
+    pub path: PathBuf,
+    /// `false` si no existe `tools.json` y se usan los valores por defecto.
+    pub exists: bool,
+    pub tools: Vec<UrlCheck>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UrlCheck {
+    pub tool: String,
+    pub version: String,
+    pub url: String,
+    pub sha256: Option<String>,
+    pub reachable: bool,
+    /// Codigo HTTP del HEAD, si el servidor respondio.
+    pub status: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// Version no interactiva del asistente: carga `tools.json` y valida sus URLs sin editar nada.
+pub fn check_manifest() -> Result<ManifestReport, BeError> {
+    let manifest_path = Path::new("tools.json");
+    let exists = manifest_path.exists();
+    let manifest = if exists {
+        Manifest::load_from_file(manifest_path)?
+    } else {
+        Manifest::default()
+    };
+    Ok(ManifestReport {
+        path: manifest_path.to_path_buf(),
+        exists,
+        tools: check_urls(&manifest),
+    })
+}
+
+fn check_urls(manifest: &Manifest) -> Vec<UrlCheck> {
+    manifest
+        .tools
+        .iter()
+        .map(|tool| {
+            let (status, error) = match http::send(|client| client.head(&tool.url)) {
+                Ok(resp) => (Some(resp.status()), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            UrlCheck {
+                tool: tool.name.clone(),
+                version: tool.version.clone(),
+                url: tool.url.clone(),
+                sha256: tool.sha256.clone(),
+                reachable: status.is_some_and(|s| s.is_success()),
+                status: status.map(|s| s.as_u16()),
+                error,
+            }
+        })
+        .collect()
+}
+
 fn validate_all_urls(manifest: &Manifest) {
     println!("\nVerificando disponibilidad de URLs (HEAD Request)...");
-    let client = reqwest::blocking::Client::new();
 
-    for tool in &manifest.tools {
-        print!("   {}: ", tool.name);
-        use std::io::Write;
-        let _ = std::io::stdout().flush();
-
-        match client.head(&tool.url).send() {
-            Ok(resp) => {
-                if resp.status().is_success() {
-                    println!("OK ({})", resp.status());
-                } else {
-                    println!("ERROR ({}) - Link Posiblemente Roto", resp.status());
-                }
-            }
-            Err(e) => {
-                println!("Fallo: {}", e);
-            }
+    for check in check_urls(manifest) {
+        match (check.status, &check.error) {
+            (Some(status), _) if check.reachable => println!("   {}: OK ({})", check.tool, status),
+            (Some(status), _) => println!(
+                "   {}: ERROR ({}) - Link Posiblemente Roto",
+                check.tool, status
+            ),
+            (None, error) => println!(
+                "   {}: Fallo: {}",
+                check.tool,
+                error.as_deref().unwrap_or_default()
+            ),
         }
     }
     println!("\n--- Verificacion completada ---\n");
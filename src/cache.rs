@@ -0,0 +1,730 @@
+use crate::config::{self, Settings};
+use crate::download;
+use crate::errors::BeError;
+use crate::manifest::{Manifest, Tool};
+use crate::say;
+use crate::ui;
+use crate::version;
+use indicatif::HumanBytes;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE: &str = "index.json";
+/// Variable de entorno que cambia la carpeta de la cache (gana sobre `config.toml`).
+pub const CACHE_DIR_VAR: &str = "BRISAS_CACHE_DIR";
+
+/// Un archivo descargado. Vive en `<cache>\<key>\<file_name>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    /// sha256 del manifiesto, o `url-<hash de la URL>` si el manifiesto no trae hash.
+    pub key: String,
+    pub tool: String,
+    pub version: String,
+    pub url: String,
+    /// Nombre original (ej. `PortableGit-2.47.1-64-bit.7z.exe`).
+    pub file_name: String,
+    pub size: u64,
+    /// sha256 calculado al descargar.
+    pub sha256: String,
+    pub fetched_at: String,
+    /// Ultima vez que una instalacion uso el archivo; decide que se borra primero.
+    #[serde(default)]
+    pub last_used: Option<String>,
+}
+
+impl CacheEntry {
+    fn last_used(&self) -> &str {
+        self.last_used.as_deref().unwrap_or(&self.fetched_at)
+    }
+}
+
+/// Cache de descargas direccionada por contenido: versiones distintas nunca comparten archivo.
+pub struct DownloadCache {
+    dir: PathBuf,
+    /// Tamano maximo en bytes (`None`: sin limite).
+    max_size: Option<u64>,
+}
+
+impl DownloadCache {
+    /// Cache configurada (ver `cache_dir`) con el limite de `config.toml`.
+    pub fn open() -> Result<Self, BeError> {
+        let settings = Settings::load()?;
+        let mut cache = Self::at(resolve_dir(&settings)?);
+        cache.max_size = match settings.cache.max_size_mb {
+            0 => None,
+            mb => Some(mb * 1024 * 1024),
+        };
+        Ok(cache)
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        DownloadCache {
+            dir,
+            max_size: None,
+        }
+    }
+
+    /// Clave de una descarga: el sha256 esperado o, sin el, un hash de la URL.
+    /// La clave es el nombre de una carpeta: un sha256 que no sea hexadecimal de 64
+    /// caracteres (ej. `""` o `"../x"`) se rechaza.
+    pub fn key(url: &str, sha256: Option<&str>) -> Result<String, BeError> {
+        let key = match sha256 {
+            Some(hash) => hash.to_lowercase(),
+            None => format!("url-{}", &hex::encode(Sha256::digest(url.as_bytes()))[..16]),
+        };
+        if !is_valid_key(&key) {
+            return Err(BeError::Config(format!(
+                "sha256 invalido para {}: '{}' (se esperan 64 caracteres hexadecimales)",
+                url,
+                sha256.unwrap_or_default()
+            )));
+        }
+        Ok(key)
+    }
+
+    /// Donde se guarda (o guardaria) una descarga.
+    pub fn path_for(&self, key: &str, file_name: &str) -> PathBuf {
+        self.dir.join(key).join(file_name)
+    }
+
+    /// Lo que Brisas creo en la carpeta: las carpetas de las claves del indice e
+    /// `index.json`. La carpeta puede ser elegida por el usuario (ej. `~/Downloads`), asi
+    /// que `be clean` borra solo esto y nunca la carpeta entera. Las claves invalidas de un
+    /// indice editado a mano (ej. `""` o `".."`) se ignoran.
+    pub fn owned_paths(&self) -> Result<(Vec<PathBuf>, Vec<PathBuf>), BeError> {
+        let dirs = self
+            .index()?
+            .keys()
+            .filter(|key| is_valid_key(key))
+            .map(|key| self.dir.join(key))
+            .filter(|dir| dir.exists())
+            .collect();
+        let index = self.dir.join(INDEX_FILE);
+        let files = if index.exists() {
+            vec![index]
+        } else {
+            Vec::new()
+        };
+        Ok((dirs, files))
+    }
+
+    /// Archivo de una entrada del indice.
+    pub fn file_of(&self, entry: &CacheEntry) -> PathBuf {
+        self.path_for(&entry.key, &entry.file_name)
+    }
+
+    /// Entrada registrada en el indice cuyo archivo sigue en disco.
+    pub fn find(&self, key: &str) -> Result<Option<(CacheEntry, PathBuf)>, BeError> {
+        Ok(self.index()?.remove(key).and_then(|entry| {
+            let path = self.file_of(&entry);
+            path.is_file().then_some((entry, path))
+        }))
+    }
+
+    /// Indice `clave -> entrada`. Sin `index.json` la cache esta vacia.
+    pub fn index(&self) -> Result<BTreeMap<String, CacheEntry>, BeError> {
+        let path = self.dir.join(INDEX_FILE);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Registra una descarga nueva y, si la cache supera el limite, borra las menos usadas.
+    /// Devuelve las entradas borradas.
+    pub fn record(&self, entry: CacheEntry) -> Result<Vec<CacheEntry>, BeError> {
+        let key = entry.key.clone();
+        let mut index = self.index()?;
+        index.insert(key.clone(), entry);
+        self.save_index(&index)?;
+        match self.max_size {
+            Some(max_size) => self.evict(max_size, &key),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Marca la entrada como usada ahora.
+    pub fn touch(&self, key: &str) -> Result<(), BeError> {
+        let mut index = self.index()?;
+        if let Some(entry) = index.get_mut(key) {
+            entry.last_used = Some(now());
+            self.save_index(&index)?;
+        }
+        Ok(())
+    }
+
+    /// Borra entradas, de la usada hace mas tiempo a la mas reciente, hasta que el total
+    /// quepa en `max_size`. `keep` (la descarga en curso) nunca se borra.
+    fn evict(&self, max_size: u64, keep: &str) -> Result<Vec<CacheEntry>, BeError> {
+        let index = self.index()?;
+        let mut total: u64 = index.values().map(|e| e.size).sum();
+        let mut candidates: Vec<&CacheEntry> = index.values().filter(|e| e.key != keep).collect();
+        candidates.sort_by(|a, b| a.last_used().cmp(b.last_used()));
+
+        let mut evicted = Vec::new();
+        for entry in candidates {
+            if total <= max_size {
+                break;
+            }
+            self.remove(&entry.key)?;
+            total = total.saturating_sub(entry.size);
+            evicted.push(entry.clone());
+        }
+        Ok(evicted)
+    }
+
+    /// Borra la entrada y su carpeta. Una clave invalida solo se quita del indice: no
+    /// nombra una carpeta de la cache.
+    pub fn remove(&self, key: &str) -> Result<(), BeError> {
+        let mut index = self.index()?;
+        index.remove(key);
+        let dir = self.dir.join(key);
+        if is_valid_key(key) && dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        self.save_index(&index)
+    }
+
+    fn save_index(&self, index: &BTreeMap<String, CacheEntry>) -> Result<(), BeError> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.dir.join(INDEX_FILE),
+            serde_json::to_string_pretty(index)?,
+        )?;
+        Ok(())
+    }
+}
+
+/// `true` para un sha256 (64 hex) o `url-` seguido de 16 hex; nada mas puede
+/// convertirse en carpeta de la cache.
+fn is_valid_key(key: &str) -> bool {
+    let is_hex = |s: &str, len: usize| {
+        s.len() == len && s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    };
+    is_hex(key, 64) || key.strip_prefix("url-").is_some_and(|h| is_hex(h, 16))
+}
+
+/// Estado del hash de un archivo en `be cache list` (sin recalcularlo; eso es `verify`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HashStatus {
+    /// Guardado bajo el sha256 del manifiesto, verificado al descargar.
+    Pinned,
+    /// El manifiesto no trae sha256 para esa URL.
+    Unpinned,
+    /// El indice lo registra pero el archivo ya no esta.
+    Missing,
+}
+
+impl HashStatus {
+    fn label(self) -> &'static str {
+        match self {
+            HashStatus::Pinned => "sha256",
+            HashStatus::Unpinned => "sin hash",
+            HashStatus::Missing => "falta",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CachedFile {
+    pub tool: String,
+    pub version: String,
+    pub file_name: String,
+    pub size: u64,
+    pub hash: HashStatus,
+    pub last_used: String,
+    pub path: PathBuf,
+}
+
+/// Resultado de `be cache list`.
+#[derive(Debug, Serialize)]
+pub struct CacheReport {
+    pub dir: PathBuf,
+    pub total_size: u64,
+    /// `None`: sin limite.
+    pub max_size: Option<u64>,
+    pub files: Vec<CachedFile>,
+}
+
+/// Un archivo que no paso `be cache verify` (ya eliminado de la cache).
+#[derive(Debug, Serialize)]
+pub struct DamagedFile {
+    pub tool: String,
+    pub version: String,
+    pub file_name: String,
+    /// `missing` o `hash-mismatch`.
+    pub problem: &'static str,
+}
+
+/// Resultado de `be cache verify`.
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    pub checked: usize,
+    pub damaged: Vec<DamagedFile>,
+}
+
+/// `be cache list`: herramienta, version, tamano y estado del hash de cada descarga.
+pub fn list() -> Result<CacheReport, BeError> {
+    let cache = DownloadCache::open()?;
+    let mut files: Vec<CachedFile> = cache
+        .index()?
+        .into_values()
+        .map(|entry| {
+            let path = cache.file_of(&entry);
+            let hash = if !path.is_file() {
+                HashStatus::Missing
+            } else if entry.key.starts_with("url-") {
+                HashStatus::Unpinned
+            } else {
+                HashStatus::Pinned
+            };
+            CachedFile {
+                last_used: entry.last_used().to_string(),
+                tool: entry.tool,
+                version: entry.version,
+                file_name: entry.file_name,
+                size: entry.size,
+                hash,
+                path,
+            }
+        })
+        .collect();
+    files.sort_by(|a, b| {
+        a.tool
+            .cmp(&b.tool)
+            .then_with(|| version::compare(&a.version, &b.version))
+    });
+    let report = CacheReport {
+        total_size: files.iter().map(|f| f.size).sum(),
+        dir: cache.dir,
+        max_size: cache.max_size,
+        files,
+    };
+
+    say!("Cache de descargas: {}", report.dir.display());
+    if report.files.is_empty() {
+        say!("  (vacia)");
+        return Ok(report);
+    }
+    for file in &report.files {
+        say!(
+            "  {:<10} {:<14} {:>10}  {:<8}  {}  {}",
+            file.tool,
+            file.version,
+            HumanBytes(file.size).to_string(),
+            file.hash.label(),
+            file.last_used,
+            file.file_name
+        );
+    }
+    let limit = match report.max_size {
+        Some(max) => HumanBytes(max).to_string(),
+        None => "sin limite".to_string(),
+    };
+    say!(
+        "Total: {} (limite: {})",
+        HumanBytes(report.total_size),
+        limit
+    );
+    Ok(report)
+}
+
+/// `be cache verify`: recalcula el sha256 de cada archivo y elimina los que no coinciden
+/// con lo registrado al descargarlos (se vuelven a descargar en el proximo `setup`).
+pub fn verify() -> Result<VerifyReport, BeError> {
+    let cache = DownloadCache::open()?;
+    let index = cache.index()?;
+    let mut report = VerifyReport {
+        checked: index.len(),
+        damaged: Vec::new(),
+    };
+    for entry in index.values() {
+        say!(
+            "Verificando {} {} ({})",
+            entry.tool,
+            entry.version,
+            entry.file_name
+        );
+        let path = cache.file_of(entry);
+        let problem = if !path.is_file() {
+            Some("missing")
+        } else if download::calculate_hash(&path)? != entry.sha256 {
+            Some("hash-mismatch")
+        } else {
+            None
+        };
+        match problem {
+            None => say!("  Hash Correcto."),
+            Some(problem) => {
+                let detail = if problem == "missing" {
+                    "Falta el archivo"
+                } else {
+                    "Hash Incorrecto"
+                };
+                say!("  {}: se elimina de la cache.", detail);
+                cache.remove(&entry.key)?;
+                report.damaged.push(DamagedFile {
+                    tool: entry.tool.clone(),
+                    version: entry.version.clone(),
+                    file_name: entry.file_name.clone(),
+                    problem,
+                });
+            }
+        }
+    }
+    if report.damaged.is_empty() {
+        ui::print_success(&format!("{} archivos verificados.", report.checked));
+    } else {
+        ui::print_error(&format!(
+            "{} de {} archivos estaban danados y se eliminaron.",
+            report.damaged.len(),
+            report.checked
+        ));
+    }
+    Ok(report)
+}
+
+/// `be cache prune`: vacia la cache; con `keep_latest` conserva la version mas nueva de
+/// cada herramienta.
+pub fn prune(keep_latest: bool) -> Result<(), BeError> {
+    let cache = DownloadCache::open()?;
+    let index = cache.index()?;
+    let mut freed = 0;
+    let mut removed = 0;
+    for entry in prune_candidates(&index, keep_latest) {
+        say!(
+            "  Eliminando {} {} ({})",
+            entry.tool,
+            entry.version,
+            entry.file_name
+        );
+        cache.remove(&entry.key)?;
+        freed += entry.size;
+        removed += 1;
+    }
+    ui::print_success(&format!(
+        "{} descargas eliminadas ({} liberados).",
+        removed,
+        HumanBytes(freed)
+    ));
+    Ok(())
+}
+
+fn prune_candidates(index: &BTreeMap<String, CacheEntry>, keep_latest: bool) -> Vec<&CacheEntry> {
+    let newest = |a: &CacheEntry, b: &CacheEntry| -> Ordering {
+        version::compare(&a.version, &b.version).then_with(|| a.last_used().cmp(b.last_used()))
+    };
+    index
+        .values()
+        .filter(|entry| {
+            !keep_latest
+                || index
+                    .values()
+                    .any(|other| other.tool == entry.tool && newest(other, entry).is_gt())
+        })
+        .collect()
+}
+
+/// `be cache import <archivo>`: copia una descarga (ej. desde un USB) a la cache si su
+/// sha256 coincide con el de alguna herramienta del manifiesto.
+pub fn import(file: &Path) -> Result<(), BeError> {
+    if !file.is_file() {
+        return Err(BeError::Config(format!(
+            "No existe el archivo {}",
+            file.display()
+        )));
+    }
+    let manifest = Manifest::load_local_or_default()?;
+    say!("Calculando sha256 de {}", file.display());
+    let hash = download::calculate_hash(file)?;
+    let tool = match manifest
+        .tools
+        .iter()
+        .find(|t| t.sha256.as_deref().map(str::to_lowercase).as_deref() == Some(hash.as_str()))
+    {
+        Some(tool) => tool,
+        None => return Err(import_mismatch(&manifest, file, &hash)),
+    };
+
+    let cache = DownloadCache::open()?;
+    let key = DownloadCache::key(&tool.url, Some(&hash))?;
+    let file_name = download::cache_file_name(tool);
+    let target = cache.path_for(&key, &file_name);
+    if let Some(dir) = target.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::copy(file, &target)?;
+    cache.record(CacheEntry {
+        key,
+        tool: tool.name.clone(),
+        version: tool.version.clone(),
+        url: tool.url.clone(),
+        file_name,
+        size: fs::metadata(&target)?.len(),
+        sha256: hash,
+        fetched_at: now(),
+        last_used: None,
+    })?;
+    ui::print_success(&format!(
+        "{} {} importado: 'be setup' ya no lo descargara.",
+        tool.name, tool.version
+    ));
+    Ok(())
+}
+
+/// Error de `import` cuando ningun hash coincide: si el nombre del archivo es el de una
+/// herramienta, explica por que no se acepta.
+fn import_mismatch(manifest: &Manifest, file: &Path, hash: &str) -> BeError {
+    let name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let by_name =
+        |t: &&Tool| download::cache_file_name(t) == name || file_name_from_url(&t.url) == name;
+    match manifest.tools.iter().find(by_name) {
+        Some(tool) => match &tool.sha256 {
+            Some(expected) => BeError::Setup(format!(
+                "{} no coincide con {} {} del manifiesto. Esperado {}, obtenido {}.",
+                name, tool.name, tool.version, expected, hash
+            )),
+            None => BeError::Config(format!(
+                "{} {} no tiene sha256 en el manifiesto: no se puede verificar {}.",
+                tool.name, tool.version, name
+            )),
+        },
+        None => BeError::Config(format!(
+            "Ningun sha256 del manifiesto coincide con {} ({}).",
+            file.display(),
+            hash
+        )),
+    }
+}
+
+/// `be cache export <carpeta>`: copia las descargas con su nombre original, listas para
+/// `be cache import` en otra maquina.
+pub fn export(dir: &Path) -> Result<(), BeError> {
+    let cache = DownloadCache::open()?;
+    fs::create_dir_all(dir)?;
+    let mut exported = 0;
+    for entry in cache.index()?.values() {
+        let source = cache.file_of(entry);
+        if !source.is_file() {
+            continue;
+        }
+        let target = dir.join(&entry.file_name);
+        say!("  {} -> {}", entry.file_name, target.display());
+        fs::copy(&source, &target)?;
+        exported += 1;
+    }
+    ui::print_success(&format!(
+        "{} descargas exportadas a {}.",
+        exported,
+        dir.display()
+    ));
+    Ok(())
+}
+
+/// Carpeta de la cache: `BRISAS_CACHE_DIR`, si no `[cache] dir` de `config.toml`, y si no
+/// `BrisasEnv\cache` (fuera de `%TEMP%`, que los limpiadores de disco vacian).
+pub fn cache_dir() -> Result<PathBuf, BeError> {
+    resolve_dir(&Settings::load()?)
+}
+
+fn resolve_dir(settings: &Settings) -> Result<PathBuf, BeError> {
+    if let Some(dir) = env::var_os(CACHE_DIR_VAR).filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    match &settings.cache.dir {
+        Some(dir) => Ok(dir.clone()),
+        None => Ok(config::state_dir()?.join("cache")),
+    }
+}
+
+/// Donde guardaban la cache las versiones anteriores; `be clean` tambien la borra.
+pub fn legacy_cache_dir() -> PathBuf {
+    env::temp_dir().join("BrisasEnv_Cache")
+}
+
+pub fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// Ultimo segmento de la URL (sin `?query`), apto como nombre de archivo en Windows.
+pub fn file_name_from_url(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let name: String = path
+        .trim_end_matches('/')
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*' => '_',
+            c => c,
+        })
+        .collect();
+    if name.is_empty() {
+        "download".to_string()
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, tool: &str, version: &str) -> CacheEntry {
+        CacheEntry {
+            key: key.into(),
+            tool: tool.into(),
+            version: version.into(),
+            url: format!("https://example.com/{}.zip", tool),
+            file_name: "f.zip".into(),
+            size: 1,
+            sha256: "x".into(),
+            fetched_at: "2025-01-01 10:00:00".into(),
+            last_used: None,
+        }
+    }
+
+    #[test]
+    fn test_key_prefers_hash_and_differs_by_url() {
+        let hash = "AB".repeat(32);
+        assert_eq!(
+            DownloadCache::key("https://x/a.zip", Some(&hash)).unwrap(),
+            "ab".repeat(32)
+        );
+        let v1 = DownloadCache::key("https://x/git-2.47.0.7z.exe", None).unwrap();
+        let v2 = DownloadCache::key("https://x/git-2.47.1.7z.exe", None).unwrap();
+        assert!(v1.starts_with("url-"));
+        assert_ne!(v1, v2);
+    }
+
+    #[test]
+    fn test_key_rejects_hashes_that_are_not_folder_safe() {
+        for bad in ["", "abc", "../x", ".", &format!("../{}", "a".repeat(61))] {
+            assert!(DownloadCache::key("https://x/a.zip", Some(bad)).is_err());
+        }
+        assert!(!is_valid_key("url-../../x"));
+    }
+
+    #[test]
+    fn test_file_name_keeps_extension() {
+        assert_eq!(
+            file_name_from_url("https://github.com/git/PortableGit-2.47.1-64-bit.7z.exe"),
+            "PortableGit-2.47.1-64-bit.7z.exe"
+        );
+        assert_eq!(
+            file_name_from_url("https://nodejs.org/dist/node-v22.12.0-linux-x64.tar.gz?x=1"),
+            "node-v22.12.0-linux-x64.tar.gz"
+        );
+        assert_eq!(file_name_from_url("https://win.rustup.rs/x86_64"), "x86_64");
+    }
+
+    #[test]
+    fn test_record_and_find() {
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let cache = DownloadCache::at(dir.path().to_path_buf());
+        let url = "https://example.com/tool-1.0.zip";
+        let key = DownloadCache::key(url, None).unwrap();
+        assert!(cache.find(&key).unwrap().is_none());
+
+        let path = cache.path_for(&key, &file_name_from_url(url));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, b"data").unwrap();
+        cache
+            .record(CacheEntry {
+                url: url.into(),
+                file_name: "tool-1.0.zip".into(),
+                size: 4,
+                ..entry(&key, "tool", "1.0")
+            })
+            .unwrap();
+        let (entry, found) = cache.find(&key).unwrap().unwrap();
+        assert_eq!(found, path);
+        assert_eq!(entry.tool, "tool");
+
+        cache.remove(&key).unwrap();
+        assert!(cache.find(&key).unwrap().is_none());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_owned_paths_skips_invalid_keys() {
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let cache = DownloadCache::at(dir.path().join("Downloads"));
+        let key = DownloadCache::key("https://example.com/tool-1.0.zip", None).unwrap();
+        fs::create_dir_all(cache.dir.join(&key)).unwrap();
+        let index: BTreeMap<String, CacheEntry> = [key.as_str(), "", "..", "."]
+            .into_iter()
+            .map(|k| entry(k, "tool", "1.0"))
+            .map(|e| (e.key.clone(), e))
+            .collect();
+        cache.save_index(&index).unwrap();
+
+        let (dirs, files) = cache.owned_paths().unwrap();
+        assert_eq!(dirs, vec![cache.dir.join(&key)]);
+        assert_eq!(files, vec![cache.dir.join(INDEX_FILE)]);
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let cache = DownloadCache {
+            dir: dir.path().to_path_buf(),
+            max_size: Some(10),
+        };
+        let key_of = |tool: &str| {
+            DownloadCache::key(&format!("https://example.com/{}.zip", tool), None).unwrap()
+        };
+        let add = |tool: &str, fetched_at: &str| {
+            let path = cache.path_for(&key_of(tool), "f.zip");
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, b"12345").unwrap();
+            cache.record(CacheEntry {
+                size: 5,
+                fetched_at: fetched_at.into(),
+                ..entry(&key_of(tool), tool, "1.0")
+            })
+        };
+        assert!(add("old", "2025-01-01 10:00:00").unwrap().is_empty());
+        assert!(add("used", "2025-01-01 09:00:00").unwrap().is_empty());
+        // "used" is older but was installed again recently
+        cache.touch(&key_of("used")).unwrap();
+
+        let evicted = add("new", "2025-01-02 10:00:00").unwrap();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].tool, "old");
+        assert!(!dir.path().join(key_of("old")).exists());
+        let index = cache.index().unwrap();
+        assert!(index.contains_key(&key_of("used")) && index.contains_key(&key_of("new")));
+    }
+
+    #[test]
+    fn test_prune_keep_latest_keeps_newest_version_per_tool() {
+        let index: BTreeMap<String, CacheEntry> = [
+            entry("a", "node", "18.20.4"),
+            entry("b", "node", "22.12.0"),
+            entry("c", "node", "9.0.0"),
+            entry("d", "git", "2.47.1"),
+        ]
+        .into_iter()
+        .map(|e| (e.key.clone(), e))
+        .collect();
+
+        let pruned: Vec<&str> = prune_candidates(&index, true)
+            .iter()
+            .map(|e| e.key.as_str())
+            .collect();
+        assert_eq!(pruned, vec!["a", "c"]);
+        assert_eq!(prune_candidates(&index, false).len(), 4);
+    }
+}
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "be")]
@@ -6,10 +7,20 @@ use clap::{Parser, Subcommand};
 #[command(disable_help_subcommand = true)]
 #[command(version)]
 pub struct Cli {
+    /// Formato de salida: texto o JSON (status, doctor, setup, clean, manifest-gen,
+    /// cache list/verify)
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Human)]
+    pub output: OutputFormat,
     #[command(subcommand)]
     pub command: Commands,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Human,
+    Json,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Ejecutar un comando en el entorno portable
@@ -19,14 +30,145 @@ pub enum Commands {
     },
     /// Iniciar una terminal con el entorno portable
     Shell,
+    /// Imprimir un script que activa el entorno portable en la terminal actual
+    Env {
+        /// Shell del script (por defecto pwsh en Windows y bash/fish segun $SHELL)
+        #[arg(long, value_enum)]
+        shell: Option<ShellKind>,
+    },
     /// Instalar herramientas en el sistema (AppData\Local)
-    Setup,
+    Setup {
+        /// Herramientas a instalar sin preguntar, separadas por coma (ej. node,git)
+        #[arg(long, value_delimiter = ',', conflicts_with_all = ["all", "profile"])]
+        tools: Vec<String>,
+        /// Instalar todas las herramientas del manifiesto
+        #[arg(long, conflicts_with = "profile")]
+        all: bool,
+        /// Instalar un perfil del manifiesto (ej. web, c-course, full)
+        #[arg(long)]
+        profile: Option<String>,
+        /// No preguntar: usar la seleccion por defecto y aceptar confirmaciones
+        #[arg(short, long)]
+        yes: bool,
+        /// Mostrar descargas, carpetas, PATH y accesos directos sin modificar nada
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Cambiar la version activa de una herramienta (sin version: listar instaladas)
+    Use {
+        /// Herramienta del manifiesto (ej. node)
+        tool: String,
+        /// Version instalada o prefijo (ej. 18 o 22.12.0)
+        version: Option<String>,
+    },
+    /// Explicar que version de cada herramienta usaran `run`/`shell` aqui y por que
+    WhichVersion {
+        /// Limitar a una herramienta (ej. node)
+        tool: Option<String>,
+    },
     /// Desinstalar herramientas y limpiar registro
-    Clean,
+    Clean {
+        /// Desinstalar solo esta herramienta (ej. git)
+        tool: Option<String>,
+        /// Mostrar que se eliminaria sin borrar nada
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Verificar estado de la instalación
     Status,
+    /// Diagnostico detallado por herramienta con soluciones concretas
+    Doctor,
+    /// Historial del PATH del usuario y restauracion de copias
+    Path {
+        #[command(subcommand)]
+        action: PathAction,
+    },
+    /// Cache de descargas: listar, verificar, podar, importar y exportar
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
     /// Ver lista de comandos y ayuda
     Help,
     /// (Admin) Generar/Actualizar el manifiesto tools.json
     ManifestGen,
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ShellKind {
+    Pwsh,
+    Cmd,
+    Bash,
+    Fish,
+}
+
+impl Commands {
+    /// Nombre del subcomando, usado en la salida JSON.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Commands::Run { .. } => "run",
+            Commands::Shell => "shell",
+            Commands::Env { .. } => "env",
+            Commands::Setup { .. } => "setup",
+            Commands::Use { .. } => "use",
+            Commands::WhichVersion { .. } => "which-version",
+            Commands::Clean { .. } => "clean",
+            Commands::Status => "status",
+            Commands::Doctor => "doctor",
+            Commands::Path { .. } => "path",
+            Commands::Cache { .. } => "cache",
+            Commands::Help => "help",
+            Commands::ManifestGen => "manifest-gen",
+        }
+    }
+
+    /// Comandos que devuelven un resultado estructurado con `--output json`.
+    pub fn supports_json(&self) -> bool {
+        matches!(
+            self,
+            Commands::Setup { .. }
+                | Commands::Clean { .. }
+                | Commands::Status
+                | Commands::Doctor
+                | Commands::ManifestGen
+                | Commands::Cache {
+                    action: CacheAction::List | CacheAction::Verify
+                }
+        )
+    }
+}
+
+#[derive(Subcommand)]
+pub enum PathAction {
+    /// Listar las copias guardadas antes de cada cambio del PATH
+    History,
+    /// Restaurar el PATH de una copia (sin id, la mas reciente)
+    Restore {
+        /// Id de la copia (ver `be path history`)
+        id: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum CacheAction {
+    /// Listar las descargas guardadas (herramienta, version, tamano, hash)
+    List,
+    /// Recalcular el sha256 de cada descarga y eliminar las danadas
+    Verify,
+    /// Vaciar la cache de descargas
+    Prune {
+        /// Conservar la version mas nueva de cada herramienta
+        #[arg(long)]
+        keep_latest: bool,
+    },
+    /// Copiar un archivo a la cache si su sha256 coincide con el manifiesto
+    Import {
+        /// Archivo descargado (ej. desde un USB)
+        file: PathBuf,
+    },
+    /// Copiar las descargas a una carpeta para importarlas en otra maquina
+    Export {
+        /// Carpeta destino
+        dir: PathBuf,
+    },
+}
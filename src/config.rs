@@ -1,41 +1,97 @@
 use crate::errors::BeError;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::env;
+use std::fs;
 use std::path::PathBuf;
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct EnvConfig {
-    #[serde(rename = "NodePath")]
-    pub node_path: Option<String>,
-    #[serde(rename = "MinGWPath")]
-    pub mingw_path: Option<String>,
-    #[serde(rename = "LastUpdated")]
-    pub last_updated: String,
+/// Archivo de configuracion del usuario, dentro de `BrisasEnv`.
+const SETTINGS_FILE: &str = "config.toml";
+
+/// Carpeta base donde se instalan las herramientas (`%LOCALAPPDATA%`).
+/// Fuera de Windows se respeta `LOCALAPPDATA` si existe y si no se usa `~/.local/share`.
+pub fn tools_root() -> Result<PathBuf, BeError> {
+    if let Ok(local) = env::var("LOCALAPPDATA") {
+        return Ok(PathBuf::from(local));
+    }
+    if cfg!(windows) {
+        return Err(BeError::Config("No se encontro %LOCALAPPDATA%".into()));
+    }
+    dirs::data_local_dir()
+        .ok_or_else(|| BeError::Config("No se encontro la carpeta de datos del usuario".into()))
 }
 
-pub fn get_env_config() -> Result<EnvConfig, BeError> {
-    // Look in AppData (Standard installation)
-    let local = env::var("LOCALAPPDATA")
-        .map_err(|_| BeError::Config("No se encontro %LOCALAPPDATA%".into()))?;
+/// Carpeta de estado de Brisas (logs, recibos de instalacion).
+pub fn state_dir() -> Result<PathBuf, BeError> {
+    Ok(tools_root()?.join("BrisasEnv"))
+}
 
-    let app_data = PathBuf::from(local);
-    let node_app = app_data.join("node");
-    let mingw_app = app_data.join("mingw64");
+/// `BrisasEnv\config.toml`. Todas las claves son opcionales:
+///
+/// ```toml
+/// [cache]
+/// dir = 'D:\BrisasCache'
+/// max_size_mb = 4096
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub cache: CacheSettings,
+}
 
-    let mut node_path = None;
-    let mut mingw_path = None;
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheSettings {
+    /// Carpeta de la cache de descargas (`BRISAS_CACHE_DIR` tiene prioridad).
+    pub dir: Option<PathBuf>,
+    /// Tamano maximo en MB; al superarlo se borran las descargas usadas hace mas tiempo.
+    /// `0` desactiva el limite.
+    pub max_size_mb: u64,
+}
 
-    if node_app.join("node.exe").exists() {
-        node_path = Some(node_app.to_string_lossy().to_string());
+impl Default for CacheSettings {
+    fn default() -> Self {
+        CacheSettings {
+            dir: None,
+            max_size_mb: 5 * 1024,
+        }
     }
-    if mingw_app.join("bin/gcc.exe").exists() {
-        mingw_path = Some(mingw_app.to_string_lossy().to_string());
+}
+
+impl Settings {
+    /// Lee `config.toml`; si no existe se usan los valores por defecto.
+    pub fn load() -> Result<Self, BeError> {
+        let path = settings_file()?;
+        if !path.exists() {
+            return Ok(Settings::default());
+        }
+        Self::parse(&fs::read_to_string(&path)?)
+            .map_err(|e| BeError::Config(format!("{} invalido: {}", path.display(), e)))
     }
 
-    // Return config struct (LastUpdated is dummy/current)
-    Ok(EnvConfig {
-        node_path,
-        mingw_path,
-        last_updated: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-    })
+    fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(content)
+    }
+}
+
+pub fn settings_file() -> Result<PathBuf, BeError> {
+    Ok(state_dir()?.join(SETTINGS_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_defaults_and_overrides() {
+        let empty = Settings::parse("").unwrap();
+        assert!(empty.cache.dir.is_none());
+        assert_eq!(empty.cache.max_size_mb, 5 * 1024);
+
+        let custom =
+            Settings::parse("[cache]\ndir = 'D:\\BrisasCache'\nmax_size_mb = 0\n").unwrap();
+        assert_eq!(custom.cache.dir, Some(PathBuf::from("D:\\BrisasCache")));
+        assert_eq!(custom.cache.max_size_mb, 0);
+
+        assert!(Settings::parse("[cache]\nmax_size = 10\n").is_err());
+    }
 }
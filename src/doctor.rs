@@ -0,0 +1,366 @@
+use crate::config;
+use crate::env_store;
+use crate::errors::BeError;
+use crate::manifest::{Manifest, Tool};
+use crate::path_list::{PathList, PathStyle};
+use crate::receipt::Receipt;
+use crate::say;
+use crate::version;
+use regex::Regex;
+use serde::Serialize;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// De donde sale el ejecutable que encuentra el PATH actual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BinarySource {
+    /// Dentro de una instalacion de Brisas.
+    Portable,
+    /// Una instalacion global que tiene prioridad (o reemplaza) a la portable.
+    Global,
+    NotFound,
+}
+
+/// Diagnostico de una herramienta del manifiesto.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolReport {
+    pub tool: String,
+    pub manifest_version: String,
+    /// Version activa segun los recibos (`be use`), si esta instalada.
+    pub active_version: Option<String>,
+    /// Version que informa `check_command`.
+    pub actual_version: Option<String>,
+    pub command: String,
+    pub binary: Option<PathBuf>,
+    pub source: BinarySource,
+    /// Si el PATH persistente del usuario tiene las entradas de la version activa.
+    pub in_user_path: Option<bool>,
+    pub fixes: Vec<String>,
+}
+
+impl ToolReport {
+    /// Instalada, portable, en el PATH del usuario y con la version esperada.
+    pub fn is_ok(&self) -> bool {
+        self.fixes.is_empty() && self.source == BinarySource::Portable
+    }
+}
+
+/// Resultado de `be status` / `be doctor`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DoctorReport {
+    /// Todas las herramientas responden desde la instalacion portable y sin soluciones pendientes.
+    pub healthy: bool,
+    /// Error al leer `tools.json`. Sin manifiesto no se diagnostica ninguna herramienta.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest_error: Option<String>,
+    pub tools: Vec<ToolReport>,
+}
+
+/// `be status` (resumen) y `be doctor` (detalle y soluciones).
+pub fn run(detailed: bool) -> Result<DoctorReport, BeError> {
+    say!("Verificando Estado del Sistema...");
+    let (tools, manifest_error) = match Manifest::load_local_or_default() {
+        Ok(manifest) => (diagnose_all(&manifest)?, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+    let report = DoctorReport {
+        healthy: manifest_error.is_none() && tools.iter().all(ToolReport::is_ok),
+        manifest_error,
+        tools,
+    };
+
+    if let Some(error) = &report.manifest_error {
+        say!("\n  [ ] {:<10} : {}", "tools.json", error);
+        if detailed {
+            say!("\ntools.json:");
+            say!("  -> Corrige el archivo (o borralo para usar el manifiesto integrado).");
+        }
+        say!("\nNo se puede diagnosticar sin un manifiesto valido.");
+        return Ok(report);
+    }
+
+    say!("\nPrueba de Ejecucion (Detecta instalaciones globales y portables):");
+    for tool in &report.tools {
+        print_summary(tool);
+    }
+
+    if detailed {
+        for tool in report.tools.iter().filter(|r| !r.fixes.is_empty()) {
+            say!("\n{}:", tool.tool);
+            for fix in &tool.fixes {
+                say!("  -> {}", fix);
+            }
+        }
+    }
+
+    say!("\nNota: Si acabas de instalar, reinicia tu terminal para recargar el PATH.");
+    if report.healthy {
+        say!("\n¡Sistema Operativo al 100%! 🚀");
+    } else if detailed {
+        say!("\nAplica las soluciones indicadas y vuelve a ejecutar 'be doctor'.");
+    } else {
+        say!("\nAlgunas herramientas no responden. Ejecuta 'be doctor' para ver como resolverlo.");
+    }
+    Ok(report)
+}
+
+/// Diagnostica cada herramienta del manifiesto contra el PATH actual y el del usuario.
+pub fn diagnose_all(manifest: &Manifest) -> Result<Vec<ToolReport>, BeError> {
+    let user_path = env_store::user_store()
+        .and_then(|store| store.read_path())
+        .ok()
+        .map(|entries| PathList::new(entries, PathStyle::native()));
+    let current_path = env::var_os("PATH").unwrap_or_default();
+    let target_base = config::tools_root()?;
+
+    manifest
+        .tools
+        .iter()
+        .map(|tool| diagnose(tool, &target_base, &current_path, user_path.as_ref()))
+        .collect()
+}
+
+fn diagnose(
+    tool: &Tool,
+    target_base: &Path,
+    current_path: &std::ffi::OsStr,
+    user_path: Option<&PathList>,
+) -> Result<ToolReport, BeError> {
+    let active = Receipt::load_active(&tool.name)?;
+    let command = check_command(tool);
+    let program = command.split_whitespace().next().unwrap_or_default();
+    let binary = find_on_path(program, current_path);
+
+    let source = match &binary {
+        None => BinarySource::NotFound,
+        Some(path) if path.starts_with(target_base.join(&tool.name)) => BinarySource::Portable,
+        Some(path) if is_under_receipt(path, &tool.name)? => BinarySource::Portable,
+        Some(_) => BinarySource::Global,
+    };
+    let actual_version = binary
+        .as_ref()
+        .and_then(|path| probe_version(path, &command, tool.version_regex.as_deref()));
+    let in_user_path = active.as_ref().and_then(|receipt| {
+        user_path.map(|path| {
+            receipt
+                .path_entries
+                .iter()
+                .all(|entry| path.contains(&entry.to_string_lossy()))
+        })
+    });
+
+    let mut report = ToolReport {
+        tool: tool.name.clone(),
+        manifest_version: tool.version.clone(),
+        active_version: active.map(|r| r.version),
+        actual_version,
+        command,
+        binary,
+        source,
+        in_user_path,
+        fixes: Vec::new(),
+    };
+    report.fixes = suggest_fixes(&report);
+    if let Some(Err(e)) = tool.version_regex.as_deref().map(Regex::new) {
+        report
+            .fixes
+            .push(format!("version_regex invalida en tools.json: {}", e));
+    }
+    Ok(report)
+}
+
+/// `check_command` del manifiesto o `<check_file sin extension> --version`.
+fn check_command(tool: &Tool) -> String {
+    if let Some(command) = &tool.check_command {
+        return command.clone();
+    }
+    let stem = Path::new(&tool.check_file)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| tool.name.clone());
+    format!("{} --version", stem)
+}
+
+/// Algunas herramientas (ej. rustup) instalan fuera de `<root>\<tool>`.
+fn is_under_receipt(binary: &Path, tool_name: &str) -> Result<bool, BeError> {
+    let style = PathStyle::native();
+    let dir = binary.parent().map(|d| d.to_string_lossy().to_string());
+    Ok(Receipt::list_versions(tool_name)?.iter().any(|receipt| {
+        receipt.path_entries.iter().any(|entry| {
+            dir.as_ref()
+                .is_some_and(|d| style.normalize(d) == style.normalize(&entry.to_string_lossy()))
+        })
+    }))
+}
+
+/// Primer ejecutable que encontraria el sistema, probando `PATHEXT` en Windows.
+fn find_on_path(program: &str, path: &std::ffi::OsStr) -> Option<PathBuf> {
+    let extensions: Vec<String> = if cfg!(windows) && Path::new(program).extension().is_none() {
+        env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".into())
+            .split(';')
+            .map(|ext| ext.to_lowercase())
+            .collect()
+    } else {
+        vec![String::new()]
+    };
+    env::split_paths(path).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", program, ext)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn probe_version(binary: &Path, command: &str, version_regex: Option<&str>) -> Option<String> {
+    let args: Vec<&str> = command.split_whitespace().skip(1).collect();
+    let output = Command::new(binary).args(&args).output().ok()?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    extract_version(&text, version_regex)
+}
+
+/// Aplica `version_regex` (o un patron generico `x.y[.z]`) a la salida del comando.
+fn extract_version(output: &str, version_regex: Option<&str>) -> Option<String> {
+    let re = Regex::new(version_regex.unwrap_or(r"(\d+\.\d+(?:\.\d+)?)")).ok()?;
+    let caps = re.captures(output)?;
+    caps.get(1)
+        .or_else(|| caps.get(0))
+        .map(|m| m.as_str().to_string())
+}
+
+fn suggest_fixes(report: &ToolReport) -> Vec<String> {
+    let mut fixes = Vec::new();
+    let name = &report.tool;
+    let program = report.command.split_whitespace().next().unwrap_or(name);
+
+    let Some(active) = &report.active_version else {
+        match &report.binary {
+            Some(binary) => fixes.push(format!(
+                "Se usa una instalacion global ({}). Para la portable: 'be setup --tools {}'.",
+                binary.display(),
+                name
+            )),
+            None => fixes.push(format!("No instalado: 'be setup --tools {}'.", name)),
+        }
+        return fixes;
+    };
+
+    if report.in_user_path == Some(false) {
+        fixes.push(format!(
+            "El PATH del usuario no tiene la version activa: 'be use {} {}'.",
+            name, active
+        ));
+    }
+    match (report.source, &report.binary) {
+        (BinarySource::NotFound, _) => fixes.push(format!(
+            "'{}' no esta en el PATH de esta terminal: reiniciala o usa 'be shell'.",
+            program
+        )),
+        (BinarySource::Global, Some(binary)) => fixes.push(format!(
+            "Un {} global en {} tiene prioridad sobre el portable: quitalo del PATH o usa \
+             'be run' / 'be shell'.",
+            program,
+            binary.parent().unwrap_or(binary).display()
+        )),
+        _ => {}
+    }
+    if report.source == BinarySource::Portable {
+        if let Some(actual) = &report.actual_version {
+            if !version::matches(active, actual) && !version::matches(actual, active) {
+                fixes.push(format!(
+                    "Se ejecuta {} pero la version activa es {}: reinicia la terminal.",
+                    actual, active
+                ));
+            }
+        }
+    }
+    if version::compare(active, &report.manifest_version) != std::cmp::Ordering::Equal {
+        fixes.push(format!(
+            "El manifiesto pide {}: 'be setup --tools {}' para instalarla.",
+            report.manifest_version, name
+        ));
+    }
+    fixes
+}
+
+fn print_summary(report: &ToolReport) {
+    let mark = if report.is_ok() {
+        "[x]"
+    } else if report.binary.is_some() {
+        "[!]"
+    } else {
+        "[ ]"
+    };
+    let detail = match (&report.binary, report.source) {
+        (None, _) => "No encontrado en PATH actual".to_string(),
+        (Some(binary), source) => format!(
+            "{} {} ({})",
+            report.actual_version.as_deref().unwrap_or("?"),
+            if source == BinarySource::Portable {
+                "portable"
+            } else {
+                "global"
+            },
+            binary.display()
+        ),
+    };
+    say!("  {} {:<10} : {}", mark, report.tool, detail);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(source: BinarySource, active: Option<&str>, actual: Option<&str>) -> ToolReport {
+        ToolReport {
+            tool: "node".into(),
+            manifest_version: "22.12.0".into(),
+            active_version: active.map(str::to_string),
+            actual_version: actual.map(str::to_string),
+            command: "node --version".into(),
+            binary: (source != BinarySource::NotFound).then(|| PathBuf::from("/x/node")),
+            source,
+            in_user_path: active.map(|_| true),
+            fixes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_extract_version_with_and_without_regex() {
+        assert_eq!(
+            extract_version("v22.12.0\n", Some(r"v(\d+\.\d+\.\d+)")),
+            Some("22.12.0".into())
+        );
+        assert_eq!(
+            extract_version("git version 2.47.1.windows.1", None),
+            Some("2.47.1".into())
+        );
+        assert_eq!(extract_version("sin version", None), None);
+    }
+
+    #[test]
+    fn test_fixes_for_healthy_portable_install() {
+        let ok = report(BinarySource::Portable, Some("22.12.0"), Some("22.12.0"));
+        assert!(suggest_fixes(&ok).is_empty());
+    }
+
+    #[test]
+    fn test_fixes_explain_shadowing_and_missing_install() {
+        let shadowed = report(BinarySource::Global, Some("22.12.0"), Some("18.0.0"));
+        let fixes = suggest_fixes(&shadowed);
+        assert_eq!(fixes.len(), 1);
+        assert!(fixes[0].contains("global"));
+
+        let missing = report(BinarySource::NotFound, None, None);
+        assert_eq!(
+            suggest_fixes(&missing),
+            vec!["No instalado: 'be setup --tools node'."]
+        );
+    }
+}
@@ -1,18 +1,40 @@
+use crate::cache::{self, CacheEntry, DownloadCache};
 use crate::errors::BeError;
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::http;
+use crate::manifest::{InstallKind, Tool};
+use crate::output;
+use crate::say;
+use indicatif::{HumanBytes, ProgressBar, ProgressDrawTarget, ProgressStyle};
 use log::{info, warn};
+use reqwest::header::{CONTENT_RANGE, ETAG, IF_RANGE, LAST_MODIFIED, RANGE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::fs::{self, File};
+use std::fs::{self, File, OpenOptions};
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
+use std::thread;
 use zip::ZipArchive;
 
+/// Barra de progreso (en stderr); oculta con `--output json`. Con `len` 0 es un spinner.
+fn progress_bar(len: u64) -> ProgressBar {
+    let pb = if len == 0 {
+        ProgressBar::new_spinner()
+    } else {
+        ProgressBar::new(len)
+    };
+    if output::is_json() {
+        pb.set_draw_target(ProgressDrawTarget::hidden());
+    }
+    pb
+}
+
 /// Calcula el hash SHA256 de un archivo y lo devuelve como string hex minúscula.
 pub fn calculate_hash(path: &Path) -> Result<String, BeError> {
     let file = File::open(path)?;
     let total_size = file.metadata()?.len();
 
-    let pb = ProgressBar::new(total_size);
+    let pb = progress_bar(total_size);
     let style = ProgressStyle::default_bar()
         .template("{spinner:.green}  [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({binary_bytes_per_sec})")
         .map_err(|e| BeError::Setup(format!("Error configurando barra de progreso: {}", e)))?
@@ -36,13 +58,13 @@ pub fn extract_zip(zip_path: &Path, extract_to: &Path) -> Result<(), BeError> {
         zip_path.display(),
         extract_to.display()
     );
-    println!("Extrayendo...");
+    say!("Extrayendo...");
 
     let file = File::open(zip_path)?;
     let mut archive = ZipArchive::new(file)?;
     let len = archive.len();
 
-    let pb = ProgressBar::new(len as u64);
+    let pb = progress_bar(len as u64);
     let style = ProgressStyle::default_bar()
         .template("{spinner:.green}  [{elapsed_precise}] [{bar:40.yellow/blue}] {pos}/{len} archivos ({eta})")
         .map_err(|e| BeError::Setup(format!("Error configurando barra de progreso: {}", e)))?
@@ -73,106 +95,338 @@ pub fn extract_zip(zip_path: &Path, extract_to: &Path) -> Result<(), BeError> {
     Ok(())
 }
 
-/// Descarga un archivo, utilizando un directorio de caché local.
-/// Si `expected_hash` es proporcionado, verifica la integridad del archivo.
-/// Devuelve la ruta al archivo válido (en caché).
-pub fn ensure_downloaded(
-    url: &str,
-    file_name: &str,
-    expected_hash: Option<&str>,
-) -> Result<PathBuf, BeError> {
-    let cache_dir = std::env::temp_dir().join("BrisasEnv_Cache");
-    if !cache_dir.exists() {
-        fs::create_dir_all(&cache_dir)?;
+/// Extrae un archivo `.tar.gz`. Las entradas que intentan salir de `extract_to` se ignoran.
+pub fn extract_tar_gz(archive_path: &Path, extract_to: &Path) -> Result<(), BeError> {
+    info!(
+        "Extrayendo {} a {}",
+        archive_path.display(),
+        extract_to.display()
+    );
+    say!("Extrayendo...");
+
+    fs::create_dir_all(extract_to)?;
+    let file = File::open(archive_path)?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+
+    // El total de entradas no se conoce sin leer todo el archivo: usar spinner
+    let pb = progress_bar(0);
+    let style = ProgressStyle::default_spinner()
+        .template("{spinner:.green}  [{elapsed_precise}] {pos} archivos")
+        .map_err(|e| BeError::Setup(format!("Error configurando barra de progreso: {}", e)))?;
+    pb.set_style(style);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        entry.unpack_in(extract_to)?;
+        pb.inc(1);
     }
+    pb.finish_with_message("Extracción completada");
+    Ok(())
+}
 
-    let target_path = cache_dir.join(file_name);
+/// Tamaño remoto segun `Content-Length` de un HEAD. `None` si el servidor no lo informa.
+pub fn remote_size(url: &str) -> Option<u64> {
+    let response = http::send(|client| client.head(url)).ok()?;
+    let response = response.error_for_status().ok()?;
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_LENGTH)?
+        .to_str()
+        .ok()?
+        .parse()
+        .ok()
+}
+
+/// Descarga el archivo de la herramienta salvo que ya este en la cache.
+/// Con `sha256` en el manifiesto se verifica tanto lo cacheado como lo descargado.
+/// Devuelve la ruta al archivo válido (en caché).
+pub fn ensure_downloaded(tool: &Tool) -> Result<PathBuf, BeError> {
+    let cache = DownloadCache::open()?;
+    let expected_hash = tool.sha256.as_deref().map(str::to_lowercase);
+    let key = DownloadCache::key(&tool.url, expected_hash.as_deref())?;
 
     // 1. Verificar si existe
-    if target_path.exists() {
-        println!("  Archivo encontrado en caché: {}", file_name);
-        info!("Archivo encontrado en caché: {}", target_path.display());
-        if let Some(hash) = expected_hash {
-            print!("  Verificando integridad (Caché)... ");
-            use std::io::Write;
-            let _ = std::io::stdout().flush();
-            let current_hash = calculate_hash(&target_path)?;
-            if current_hash == hash {
-                println!("  Hash Correcto.");
-                info!("¡Hash correcto! Usando archivo en caché.");
-                return Ok(target_path);
-            } else {
-                println!("  Hash Incorrecto.");
-                println!("    Esperado: {}", hash);
-                println!("    Obtenido: {}", current_hash);
+    if let Some((entry, cached)) = cache.find(&key)? {
+        say!("  Archivo encontrado en caché: {}", entry.file_name);
+        info!("Archivo encontrado en caché: {}", cached.display());
+        match &expected_hash {
+            Some(hash) => {
+                let current_hash = calculate_hash(&cached)?;
+                if current_hash == *hash {
+                    say!("  Verificando integridad (Caché)... Hash Correcto.");
+                    info!("¡Hash correcto! Usando archivo en caché.");
+                    cache.touch(&key)?;
+                    return Ok(cached);
+                }
+                say!("  Verificando integridad (Caché)... Hash Incorrecto.");
+                say!("    Esperado: {}", hash);
+                say!("    Obtenido: {}", current_hash);
                 warn!("Hash incorrecto en caché. Eliminando y re-descargando.");
                 warn!("Esperado: {}", hash);
                 warn!("Obtenido: {}", current_hash);
-                fs::remove_file(&target_path)?;
+                cache.remove(&key)?;
+            }
+            None => {
+                // La clave incluye la URL: una version nueva nunca reutiliza el archivo viejo
+                info!("Sin hash para verificar. Usando archivo en caché.");
+                say!("  Usando caché (Sin verificación de hash).");
+                cache.touch(&key)?;
+                return Ok(cached);
             }
-        } else {
-            // Sin hash proporcionado, asumir que el caché está bien
-            info!("Sin hash para verificar. Usando archivo en caché.");
-            println!("  Usando caché (Sin verificación de hash).");
-            return Ok(target_path);
         }
     }
 
     // 2. Descargar
-    download_file(url, &target_path)?;
+    let file_name = cache_file_name(tool);
+    let target_path = cache.path_for(&key, &file_name);
+    if let Some(dir) = target_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    download_file(&tool.url, &target_path)?;
 
     // 3. Verificar después de descargar
-    if let Some(hash) = expected_hash {
-        print!("  Verificando integridad (Descarga)... ");
-        use std::io::Write;
-        let _ = std::io::stdout().flush();
-
-        let current_hash = calculate_hash(&target_path)?;
-        if current_hash != hash {
-            println!("   Hash Incorrecto.");
-            println!("    Esperado: {}", hash);
-            println!("    Obtenido: {}", current_hash);
-            fs::remove_file(&target_path)?; // Eliminar archivo malo
+    let current_hash = calculate_hash(&target_path)?;
+    if let Some(hash) = &expected_hash {
+        if current_hash != *hash {
+            say!("  Verificando integridad (Descarga)... Hash Incorrecto.");
+            say!("    Esperado: {}", hash);
+            say!("    Obtenido: {}", current_hash);
+            cache.remove(&key)?; // Eliminar archivo malo
             return Err(BeError::Setup(format!(
                 "Falló la verificación de integridad para {}. Esperado {}, obtenido {}.",
-                file_name, hash, current_hash
+                tool.name, hash, current_hash
             )));
         }
-        println!(" Hash Correcto.");
+        say!("  Verificando integridad (Descarga)... Hash Correcto.");
         info!("Verificación exitosa.");
     }
 
+    let evicted = cache.record(CacheEntry {
+        key,
+        tool: tool.name.clone(),
+        version: tool.version.clone(),
+        url: tool.url.clone(),
+        file_name,
+        size: fs::metadata(&target_path)?.len(),
+        sha256: current_hash,
+        fetched_at: cache::now(),
+        last_used: None,
+    })?;
+    for entry in evicted {
+        info!(
+            "Cache llena: eliminado {} {} ({}).",
+            entry.tool, entry.version, entry.file_name
+        );
+    }
     Ok(target_path)
 }
 
+/// Nombre original de la descarga. Los instaladores necesitan `.exe` para ejecutarse en
+/// Windows aunque la URL no lo tenga (ej. `https://win.rustup.rs/x86_64`).
+pub fn cache_file_name(tool: &Tool) -> String {
+    let name = cache::file_name_from_url(&tool.url);
+    let runnable = matches!(
+        tool.kind,
+        InstallKind::ExeInstaller | InstallKind::SelfExtracting7z
+    );
+    if runnable && cfg!(windows) && !name.to_lowercase().ends_with(".exe") {
+        format!("{}.exe", name)
+    } else {
+        name
+    }
+}
+
+/// Datos de una descarga a medias, junto al `.part` (`<archivo>.part.json`).
+#[derive(Debug, Serialize, Deserialize)]
+struct PartInfo {
+    url: String,
+    /// ETag fuerte o `Last-Modified` de la respuesta; va en `If-Range` al reanudar.
+    validator: Option<String>,
+    /// Tamano total informado por el servidor.
+    total: Option<u64>,
+}
+
+/// `<archivo>.part` y `<archivo>.part.json`.
+fn part_paths(target_path: &Path) -> (PathBuf, PathBuf) {
+    let mut part = target_path.as_os_str().to_owned();
+    part.push(".part");
+    let mut meta = part.clone();
+    meta.push(".json");
+    (PathBuf::from(part), PathBuf::from(meta))
+}
+
+/// Descarga a `<archivo>.part` y solo al terminar lo renombra a `target_path`. Si quedo un
+/// `.part` de la misma URL se reanuda con `Range`; si el servidor no lo soporta o el archivo
+/// cambio (`If-Range`) se empieza de cero. Si la conexion se corta a mitad del archivo se
+/// reanuda en el acto, con la misma politica de reintentos que `http::send`; tras el ultimo
+/// intento el `.part` queda para la proxima ejecucion.
 pub fn download_file(url: &str, target_path: &Path) -> Result<(), BeError> {
-    println!("Descargando: {}", url);
+    say!("Descargando: {}", url);
     info!("Descargando {} a {}", url, target_path.display());
 
-    let mut response = reqwest::blocking::get(url)?;
-
-    // Verificar estado convirtiendo a error directamente si es necesario
-    if let Err(e) = response.error_for_status_ref() {
-        return Err(BeError::Reqwest(e));
+    let mut delay = http::BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let problem = match download_attempt(url, target_path)? {
+            Attempt::Done => return Ok(()),
+            Attempt::Interrupted(problem) => problem,
+        };
+        if attempt == http::MAX_ATTEMPTS {
+            warn!("{}: {} (sin mas reintentos)", url, problem);
+            return Err(BeError::Setup(format!(
+                "Descarga incompleta de {}: {}. Vuelve a ejecutar el comando para reanudarla.",
+                url, problem
+            )));
+        }
+        warn!(
+            "{}: {}. Reanudando en {}s...",
+            url,
+            problem,
+            delay.as_secs()
+        );
+        say!(
+            "  Conexion interrumpida; reanudando (intento {}/{})...",
+            attempt + 1,
+            http::MAX_ATTEMPTS
+        );
+        thread::sleep(delay);
+        delay *= 2;
+        attempt += 1;
     }
+}
 
-    let total_size = match response.content_length() {
-        Some(len) => len,
+/// Resultado de un intento de `download_file`.
+enum Attempt {
+    Done,
+    /// La conexion se corto o trajo menos bytes de los anunciados; el `.part` sigue ahi.
+    Interrupted(String),
+}
+
+/// Un 206 o 416 sin haber pedido rango: empezar de cero no cambiaria la respuesta.
+fn unexpected_range(url: &str, status: StatusCode) -> BeError {
+    BeError::Setup(format!(
+        "El servidor respondio {} a {} sin que se pidiera un rango.",
+        status, url
+    ))
+}
+
+/// Solo reintenta desde cero (una vez) si lo que fallo fue reanudar un `.part`.
+fn download_attempt(url: &str, target_path: &Path) -> Result<Attempt, BeError> {
+    let (part_path, meta_path) = part_paths(target_path);
+    let previous = read_part_info(&meta_path).filter(|info| info.url == url);
+    let mut offset = match &previous {
+        Some(_) => fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0),
         None => 0,
     };
-    let pb = ProgressBar::new(total_size);
+
+    if offset > 0 {
+        info!("Reanudando {} desde el byte {}", url, offset);
+    }
+    let validator = previous.as_ref().and_then(|p| p.validator.as_deref());
+    let mut response = http::send(|client| {
+        let request = client.get(url);
+        if offset == 0 {
+            return request;
+        }
+        let request = request.header(RANGE, format!("bytes={}-", offset));
+        match validator {
+            Some(validator) => request.header(IF_RANGE, validator),
+            None => request,
+        }
+    })?;
+
+    let total = if response.status() == StatusCode::PARTIAL_CONTENT {
+        let range = response
+            .headers()
+            .get(CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(parse_content_range);
+        let expected = previous.as_ref().and_then(|p| p.total);
+        match range {
+            Some((start, total))
+                if start == offset && (expected.is_none() || total == expected) =>
+            {
+                say!("  Reanudando desde {}.", HumanBytes(offset));
+                total.or(expected)
+            }
+            _ if offset == 0 => return Err(unexpected_range(url, response.status())),
+            _ => {
+                // The server answered a different range: the .part cannot be trusted
+                warn!(
+                    "Respuesta parcial inesperada para {}. Se descarga de nuevo.",
+                    url
+                );
+                remove_part(&part_path, &meta_path)?;
+                return download_attempt(url, target_path);
+            }
+        }
+    } else if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        if offset == 0 {
+            return Err(unexpected_range(url, response.status()));
+        }
+        warn!(
+            "El servidor rechazo el rango de {}. Se descarga de nuevo.",
+            url
+        );
+        remove_part(&part_path, &meta_path)?;
+        return download_attempt(url, target_path);
+    } else {
+        // Verificar estado convirtiendo a error directamente si es necesario
+        if let Err(e) = response.error_for_status_ref() {
+            return Err(BeError::Reqwest(e));
+        }
+        if offset > 0 {
+            info!("El servidor no reanuda {} (o el archivo cambio).", url);
+        }
+        offset = 0;
+        response.content_length()
+    };
+
+    let validator = response
+        .headers()
+        .get(ETAG)
+        .and_then(|v| v.to_str().ok())
+        .filter(|etag| !etag.starts_with("W/"))
+        .or_else(|| {
+            response
+                .headers()
+                .get(LAST_MODIFIED)
+                .and_then(|v| v.to_str().ok())
+        })
+        .map(str::to_string);
+    fs::write(
+        &meta_path,
+        serde_json::to_string_pretty(&PartInfo {
+            url: url.to_string(),
+            validator,
+            total,
+        })?,
+    )?;
+
+    let pb = progress_bar(total.unwrap_or(0));
     let style = ProgressStyle::default_bar()
         .template("{spinner:.green}  [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({binary_bytes_per_sec}, ETA {eta})")
         .map_err(|e| BeError::Setup(format!("Error configurando barra de progreso: {}", e)))?
         .progress_chars("█░");
     pb.set_style(style);
 
-    let mut file = File::create(target_path)?;
+    let mut file = if offset > 0 {
+        OpenOptions::new().append(true).open(&part_path)?
+    } else {
+        File::create(&part_path)?
+    };
 
-    let mut downloaded: u64 = 0;
+    let mut downloaded: u64 = offset;
+    pb.set_position(downloaded);
     let mut buffer = [0; 8192];
     loop {
-        let bytes_read = response.read(&mut buffer)?;
+        let bytes_read = match response.read(&mut buffer) {
+            Ok(bytes_read) => bytes_read,
+            Err(e) => {
+                pb.abandon();
+                return Ok(Attempt::Interrupted(e.to_string()));
+            }
+        };
         if bytes_read == 0 {
             break;
         }
@@ -180,11 +434,50 @@ pub fn download_file(url: &str, target_path: &Path) -> Result<(), BeError> {
         downloaded += bytes_read as u64;
         pb.set_position(downloaded);
     }
+    file.flush()?;
+    drop(file);
 
+    if let Some(total) = total {
+        if downloaded != total {
+            pb.abandon();
+            return Ok(Attempt::Interrupted(format!(
+                "{} de {} bytes",
+                downloaded, total
+            )));
+        }
+    }
+
+    fs::rename(&part_path, target_path)?;
+    fs::remove_file(&meta_path)?;
     pb.finish_with_message("Descarga completada");
+    Ok(Attempt::Done)
+}
+
+fn read_part_info(meta_path: &Path) -> Option<PartInfo> {
+    let content = fs::read_to_string(meta_path).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn remove_part(part_path: &Path, meta_path: &Path) -> Result<(), BeError> {
+    for path in [part_path, meta_path] {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
     Ok(())
 }
 
+/// `Content-Range: bytes <inicio>-<fin>/<total>` -> `(inicio, total)`; total `*` es `None`.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let start = range.split_once('-')?.0.trim().parse().ok()?;
+    let total = match total.trim() {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((start, total))
+}
+
 /// Verifica si un nombre de archivo dado existe dentro del archivo zip.
 /// Devuelve Ok(true) si se encuentra, Ok(false) si no.
 pub fn verify_zip_contains_file(zip_path: &Path, file_name: &str) -> Result<bool, BeError> {
@@ -230,4 +523,139 @@ mod tests {
         let _ = std::fs::remove_file(file_path);
         let _ = std::fs::remove_dir(dir);
     }
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(
+            parse_content_range("bytes 100-199/200"),
+            Some((100, Some(200)))
+        );
+        assert_eq!(parse_content_range("bytes 5-9/*"), Some((5, None)));
+        assert_eq!(parse_content_range("items 0-1/2"), None);
+    }
+
+    /// Serves `body` once, honoring `Range: bytes=<n>-` like a resumable server.
+    /// The thread returns the requested start.
+    fn serve_once(body: &'static [u8]) -> (String, std::thread::JoinHandle<Option<usize>>) {
+        use std::io::{BufRead, BufReader};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tool.zip", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut start = None;
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some(value) = line.to_lowercase().strip_prefix("range: bytes=") {
+                    start = value.trim().trim_end_matches('-').parse::<usize>().ok();
+                }
+            }
+            let head = match start {
+                Some(start) => format!(
+                    "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {}-{}/{}\r\n\
+                     Content-Length: {}\r\nETag: \"v1\"\r\n\r\n",
+                    start,
+                    body.len() - 1,
+                    body.len(),
+                    body.len() - start
+                ),
+                None => format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"v1\"\r\n\r\n",
+                    body.len()
+                ),
+            };
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(&body[start.unwrap_or(0)..]).unwrap();
+            start
+        });
+        (url, server)
+    }
+
+    #[test]
+    fn test_download_resumes_part_file() {
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let target = dir.path().join("tool.zip");
+        let (url, server) = serve_once(b"0123456789");
+        let (part, meta) = part_paths(&target);
+        std::fs::write(&part, b"0123").unwrap();
+        std::fs::write(
+            &meta,
+            serde_json::to_string(&PartInfo {
+                url: url.clone(),
+                validator: Some("\"v1\"".into()),
+                total: Some(10),
+            })
+            .unwrap(),
+        )
+        .unwrap();
+
+        download_file(&url, &target).expect("Deberia reanudar la descarga");
+        assert_eq!(server.join().unwrap(), Some(4));
+        assert_eq!(std::fs::read(&target).unwrap(), b"0123456789");
+        assert!(!part.exists() && !meta.exists());
+    }
+
+    #[test]
+    fn test_download_resumes_after_dropped_connection() {
+        use std::io::Read as _;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tool.zip", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            // First connection: announces 10 bytes, sends 4 and hangs up
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\nETag: \"v1\"\r\n\r\n0123")
+                .unwrap();
+            drop(stream);
+            // Second connection: must ask for the rest
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0; 1024];
+            let read = stream.read(&mut request).unwrap();
+            let request = String::from_utf8_lossy(&request[..read]).to_lowercase();
+            stream
+                .write_all(
+                    b"HTTP/1.1 206 Partial Content\r\nContent-Range: bytes 4-9/10\r\n\
+                      Content-Length: 6\r\nETag: \"v1\"\r\n\r\n456789",
+                )
+                .unwrap();
+            request.contains("range: bytes=4-")
+        });
+
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let target = dir.path().join("tool.zip");
+        download_file(&url, &target).expect("Deberia reanudar tras el corte");
+        assert!(server.join().unwrap());
+        assert_eq!(std::fs::read(&target).unwrap(), b"0123456789");
+    }
+
+    #[test]
+    fn test_download_rejects_range_status_without_range() {
+        use std::io::Read as _;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tool.zip", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]).unwrap();
+            stream
+                .write_all(b"HTTP/1.1 416 Range Not Satisfiable\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        });
+
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let target = dir.path().join("tool.zip");
+        let error = download_file(&url, &target).expect_err("No deberia reintentar sin fin");
+        assert!(error.to_string().contains("416"));
+        server.join().unwrap();
+    }
 }
@@ -0,0 +1,454 @@
+use crate::errors::BeError;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Donde se guardan los cambios persistentes del entorno del usuario (por ahora, el PATH).
+/// En Windows es el registro; en Linux/macOS un bloque marcado en los archivos del shell.
+pub trait EnvStore {
+    /// Entradas del PATH persistente, en orden.
+    fn read_path(&self) -> Result<Vec<String>, BeError>;
+    fn write_path(&mut self, entries: &[String]) -> Result<(), BeError>;
+    /// `true` si el valor es el PATH completo del usuario (registro) y no solo
+    /// las entradas que agrega Brisas. Vaciarlo por error dejaria el sistema sin PATH.
+    fn holds_full_path(&self) -> bool;
+    /// Descripcion para mensajes (ej. `HKCU\Environment`).
+    fn location(&self) -> String;
+    /// Tipo con el que esta guardado el valor (ej. `REG_EXPAND_SZ`), si el almacen lo distingue.
+    fn value_type(&self) -> Result<Option<String>, BeError> {
+        Ok(None)
+    }
+    /// Forma con la que se guarda una entrada (ej. `%LOCALAPPDATA%\node` en el registro).
+    fn portable_entry(&self, entry: &str) -> String {
+        entry.to_string()
+    }
+    /// Largo maximo recomendado del valor unido con `;`, si el almacen tiene limite.
+    fn max_path_len(&self) -> Option<usize> {
+        None
+    }
+    /// Escribe las entradas con un tipo concreto (lo usa `be path restore`).
+    fn restore_path(
+        &mut self,
+        entries: &[String],
+        _value_type: Option<&str>,
+    ) -> Result<(), BeError> {
+        self.write_path(entries)
+    }
+}
+
+/// Almacen del usuario actual segun la plataforma.
+pub fn user_store() -> Result<Box<dyn EnvStore>, BeError> {
+    #[cfg(windows)]
+    {
+        Ok(Box::new(RegistryStore))
+    }
+    #[cfg(not(windows))]
+    {
+        let home = dirs::home_dir()
+            .ok_or_else(|| BeError::Config("No se encontro la carpeta personal ($HOME)".into()))?;
+        Ok(Box::new(ProfileStore::detect(&home)))
+    }
+}
+
+/// `HKCU\Environment\Path`.
+#[cfg(windows)]
+pub struct RegistryStore;
+
+#[cfg(windows)]
+impl RegistryStore {
+    fn open(flags: u32) -> Result<winreg::RegKey, BeError> {
+        winreg::RegKey::predef(winreg::enums::HKEY_CURRENT_USER)
+            .open_subkey_with_flags("Environment", flags)
+            .map_err(|e| BeError::Setup(format!("Error abriendo registro: {}", e)))
+    }
+
+    fn write_typed(&self, value: &str, vtype: winreg::enums::RegType) -> Result<(), BeError> {
+        use winreg::enums::*;
+        // REG_SZ / REG_EXPAND_SZ are NUL-terminated UTF-16
+        let bytes: Vec<u8> = value
+            .encode_utf16()
+            .chain(Some(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        Self::open(KEY_READ | KEY_WRITE)?
+            .set_raw_value("Path", &winreg::RegValue { bytes, vtype })
+            .map_err(|e| BeError::Setup(format!("Error escribiendo registro: {}", e)))
+    }
+}
+
+#[cfg(windows)]
+impl EnvStore for RegistryStore {
+    fn read_path(&self) -> Result<Vec<String>, BeError> {
+        use winreg::enums::*;
+        let value: String = Self::open(KEY_READ)?.get_value("Path").unwrap_or_default();
+        if value.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(value.split(';').map(str::to_string).collect())
+    }
+
+    /// Conserva REG_EXPAND_SZ; si alguna entrada usa `%VAR%` el valor debe serlo para expandirse.
+    fn write_path(&mut self, entries: &[String]) -> Result<(), BeError> {
+        use winreg::enums::*;
+        let expandable = self.value_type()?.as_deref() == Some("REG_EXPAND_SZ")
+            || entries.iter().any(|e| e.contains('%'));
+        let vtype = if expandable { REG_EXPAND_SZ } else { REG_SZ };
+        self.write_typed(&entries.join(";"), vtype)
+    }
+
+    fn portable_entry(&self, entry: &str) -> String {
+        let vars: Vec<(String, String)> = ["LOCALAPPDATA", "USERPROFILE"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok().map(|v| (name.to_string(), v)))
+            .collect();
+        crate::path_list::contract_vars(entry, &vars)
+    }
+
+    /// Las variables de entorno de mas de 2047 caracteres dan problemas a `setx`,
+    /// al dialogo de Windows y a programas viejos.
+    fn max_path_len(&self) -> Option<usize> {
+        Some(2047)
+    }
+
+    fn value_type(&self) -> Result<Option<String>, BeError> {
+        use winreg::enums::*;
+        Ok(Self::open(KEY_READ)?
+            .get_raw_value("Path")
+            .ok()
+            .map(|value| format!("{:?}", value.vtype)))
+    }
+
+    fn restore_path(
+        &mut self,
+        entries: &[String],
+        value_type: Option<&str>,
+    ) -> Result<(), BeError> {
+        use winreg::enums::*;
+        let vtype = match value_type {
+            Some("REG_EXPAND_SZ") => REG_EXPAND_SZ,
+            _ => REG_SZ,
+        };
+        self.write_typed(&entries.join(";"), vtype)
+    }
+
+    fn holds_full_path(&self) -> bool {
+        true
+    }
+
+    fn location(&self) -> String {
+        "Registro (HKCU\\Environment)".to_string()
+    }
+}
+
+const BLOCK_START: &str = "# >>> brisas >>>";
+const BLOCK_END: &str = "# <<< brisas <<<";
+const BLOCK_NOTE: &str = "# Gestionado por 'be' (Brisas CLI). No editar a mano.";
+
+/// Bloque marcado en `~/.profile`, `~/.bashrc` y la configuracion de fish.
+/// Solo guarda las entradas de Brisas, que se anteponen al PATH del sistema.
+#[cfg_attr(windows, allow(dead_code))]
+pub struct ProfileStore {
+    files: Vec<ProfileFile>,
+}
+
+#[cfg_attr(windows, allow(dead_code))]
+struct ProfileFile {
+    path: PathBuf,
+    fish: bool,
+    /// `.profile` se crea si no existe; los demas solo se editan si ya estan.
+    create: bool,
+}
+
+#[cfg_attr(windows, allow(dead_code))]
+impl ProfileStore {
+    pub fn detect(home: &Path) -> Self {
+        let fish_dir = home.join(".config").join("fish");
+        let uses_fish = std::env::var("SHELL").is_ok_and(|s| s.ends_with("fish"));
+        ProfileStore {
+            files: vec![
+                ProfileFile {
+                    path: home.join(".profile"),
+                    fish: false,
+                    create: true,
+                },
+                ProfileFile {
+                    path: home.join(".bashrc"),
+                    fish: false,
+                    create: false,
+                },
+                ProfileFile {
+                    path: fish_dir.join("config.fish"),
+                    fish: true,
+                    create: uses_fish || fish_dir.exists(),
+                },
+            ],
+        }
+    }
+}
+
+impl EnvStore for ProfileStore {
+    fn read_path(&self) -> Result<Vec<String>, BeError> {
+        for file in &self.files {
+            if !file.path.exists() {
+                continue;
+            }
+            let content = fs::read_to_string(&file.path)?;
+            if let Some(entries) = parse_block(&content, file.fish) {
+                return Ok(entries);
+            }
+        }
+        Ok(Vec::new())
+    }
+
+    fn write_path(&mut self, entries: &[String]) -> Result<(), BeError> {
+        for file in &self.files {
+            let exists = file.path.exists();
+            if !exists && (!file.create || entries.is_empty()) {
+                continue;
+            }
+            let content = if exists {
+                fs::read_to_string(&file.path)?
+            } else {
+                String::new()
+            };
+            let block = (!entries.is_empty()).then(|| render_block(entries, file.fish));
+            let updated = replace_block(&content, block.as_deref());
+            if updated != content {
+                if let Some(dir) = file.path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(&file.path, updated)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn holds_full_path(&self) -> bool {
+        false
+    }
+
+    fn location(&self) -> String {
+        let files: Vec<String> = self
+            .files
+            .iter()
+            .filter(|f| f.path.exists() || f.create)
+            .map(|f| f.path.display().to_string())
+            .collect();
+        files.join(", ")
+    }
+}
+
+/// Almacen en memoria para pruebas. Con `vars` y `max_len` imita al registro.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    pub path: Vec<String>,
+    pub writes: usize,
+    pub vars: Vec<(String, String)>,
+    pub max_len: Option<usize>,
+}
+
+#[cfg(test)]
+impl EnvStore for MemoryStore {
+    fn read_path(&self) -> Result<Vec<String>, BeError> {
+        Ok(self.path.clone())
+    }
+
+    fn write_path(&mut self, entries: &[String]) -> Result<(), BeError> {
+        self.path = entries.to_vec();
+        self.writes += 1;
+        Ok(())
+    }
+
+    fn holds_full_path(&self) -> bool {
+        true
+    }
+
+    fn location(&self) -> String {
+        "memoria".to_string()
+    }
+
+    fn portable_entry(&self, entry: &str) -> String {
+        crate::path_list::contract_vars(entry, &self.vars)
+    }
+
+    fn max_path_len(&self) -> Option<usize> {
+        self.max_len
+    }
+}
+
+fn render_block(entries: &[String], fish: bool) -> String {
+    let line = if fish {
+        let quoted: Vec<String> = entries
+            .iter()
+            .map(|e| format!("'{}'", e.replace('\\', "\\\\").replace('\'', "\\'")))
+            .collect();
+        format!("set -gx PATH {} $PATH", quoted.join(" "))
+    } else {
+        let escaped: Vec<String> = entries.iter().map(|e| escape_sh(e)).collect();
+        format!("export PATH=\"{}:$PATH\"", escaped.join(":"))
+    };
+    format!("{}\n{}\n{}\n{}\n", BLOCK_START, BLOCK_NOTE, line, BLOCK_END)
+}
+
+/// Inserta, reemplaza o (con `None`) quita el bloque, dejando intacto el resto del archivo.
+fn replace_block(content: &str, block: Option<&str>) -> String {
+    let mut out = String::new();
+    let mut inside = false;
+    let mut placed = false;
+    for line in content.lines() {
+        if line.trim() == BLOCK_START {
+            inside = true;
+            match block {
+                Some(block) => {
+                    out.push_str(block);
+                    placed = true;
+                }
+                // Drop the blank line that separated the block
+                None if out.ends_with("\n\n") => {
+                    out.pop();
+                }
+                None => {}
+            }
+            continue;
+        }
+        if inside {
+            if line.trim() == BLOCK_END {
+                inside = false;
+            }
+            continue;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    if let (Some(block), false) = (block, placed) {
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+        out.push_str(block);
+    }
+    out
+}
+
+fn parse_block(content: &str, fish: bool) -> Option<Vec<String>> {
+    let mut lines = content.lines().skip_while(|l| l.trim() != BLOCK_START);
+    lines.next()?;
+    for line in lines.take_while(|l| l.trim() != BLOCK_END) {
+        let line = line.trim();
+        if fish {
+            if let Some(rest) = line.strip_prefix("set -gx PATH ") {
+                return Some(parse_fish_words(rest.trim_end_matches("$PATH")));
+            }
+        } else if let Some(rest) = line.strip_prefix("export PATH=\"") {
+            let value = rest.strip_suffix(":$PATH\"")?;
+            return Some(split_sh(value));
+        }
+    }
+    Some(Vec::new())
+}
+
+fn escape_sh(value: &str) -> String {
+    let mut out = String::new();
+    for c in value.chars() {
+        if matches!(c, '\\' | '"' | '$' | '`') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Separa por `:` deshaciendo el escape de `escape_sh`.
+fn split_sh(value: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => current.extend(chars.next()),
+            ':' => entries.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    entries.push(current);
+    entries
+}
+
+fn parse_fish_words(value: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\'' {
+            continue;
+        }
+        let mut word = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => word.extend(chars.next()),
+                '\'' => break,
+                _ => word.push(c),
+            }
+        }
+        entries.push(word);
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_sh_block_round_trip() {
+        let path = entries(&[
+            "/home/ana/.local/share/node/22.12.0/bin",
+            "/opt/we\"ird $dir",
+        ]);
+        let content = replace_block("alias ll='ls -l'\n", Some(&render_block(&path, false)));
+        assert!(content.starts_with("alias ll='ls -l'\n\n# >>> brisas >>>"));
+        assert_eq!(parse_block(&content, false), Some(path));
+    }
+
+    #[test]
+    fn test_fish_block_round_trip() {
+        let path = entries(&["/home/ana/bin", "/opt/it's"]);
+        let content = replace_block("", Some(&render_block(&path, true)));
+        assert_eq!(parse_block(&content, true), Some(path));
+    }
+
+    #[test]
+    fn test_replace_block_keeps_rest_of_file() {
+        let original = "export EDITOR=vim\n";
+        let with_block = replace_block(original, Some(&render_block(&entries(&["/a"]), false)));
+        let updated = replace_block(&with_block, Some(&render_block(&entries(&["/b"]), false)));
+        assert_eq!(parse_block(&updated, false), Some(entries(&["/b"])));
+        assert_eq!(updated.matches(BLOCK_START).count(), 1);
+
+        let removed = replace_block(&updated, None);
+        assert_eq!(removed.trim_end(), original.trim_end());
+        assert_eq!(parse_block(&removed, false), None);
+    }
+
+    #[test]
+    fn test_profile_store_writes_existing_files_only() {
+        let home = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        fs::write(home.path().join(".bashrc"), "# bashrc\n").unwrap();
+        let mut store = ProfileStore::detect(home.path());
+
+        assert!(store.read_path().unwrap().is_empty());
+        store.write_path(&entries(&["/x/bin"])).unwrap();
+        assert_eq!(store.read_path().unwrap(), entries(&["/x/bin"]));
+        let bashrc = fs::read_to_string(home.path().join(".bashrc")).unwrap();
+        assert!(bashrc.starts_with("# bashrc\n") && bashrc.contains("/x/bin"));
+
+        store.write_path(&[]).unwrap();
+        assert!(store.read_path().unwrap().is_empty());
+        assert_eq!(
+            fs::read_to_string(home.path().join(".bashrc")).unwrap(),
+            "# bashrc\n"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use crate::config;
+use crate::errors::BeError;
+use crate::installer;
+use crate::manifest::{Manifest, Tool};
+use crate::project::{ProjectRequirements, Selected};
+use crate::receipt::Receipt;
+use log::info;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Entorno de `be run`, `be shell` y el menu: las carpetas y variables de cada herramienta
+/// instalada, en el orden del manifiesto.
+#[derive(Debug, Default)]
+pub struct Environment {
+    /// `(herramienta, version)` incluidas.
+    pub tools: Vec<(String, String)>,
+    /// Carpetas que se anteponen al PATH del proceso hijo.
+    pub path_entries: Vec<PathBuf>,
+    /// Variables declaradas por las herramientas (ej. `CC`) y por el proyecto
+    /// (ej. `RUSTUP_TOOLCHAIN`).
+    pub vars: Vec<(String, String)>,
+}
+
+impl Environment {
+    /// Las versiones salen de `.brisas.toml` y de los archivos de otros gestores (`.nvmrc`,
+    /// `.tool-versions`, ...) del directorio actual o sus padres; lo que no se mencione usa
+    /// la version activa (ver `be use`). Con `.brisas.toml` solo entra lo declarado.
+    pub fn load() -> Result<Self, BeError> {
+        let found = ProjectRequirements::discover(&env::current_dir()?)?;
+        if let Some(project_file) = &found.project_file {
+            info!("Usando versiones de {}", project_file.display());
+        }
+        let manifest = Manifest::load_local_or_default()?;
+        let selected = found.resolve_for_run(&manifest)?;
+        let mut environment = Self::build(selected, &manifest);
+        if found.project_file.is_none() {
+            environment.add_unselected(&manifest, &config::tools_root()?)?;
+        }
+        Ok(environment)
+    }
+
+    /// Ordena lo elegido segun el manifiesto; las herramientas que ya no figuran van al final.
+    fn build(mut selected: Vec<Selected>, manifest: &Manifest) -> Self {
+        let position = |name: &str| {
+            manifest
+                .tools
+                .iter()
+                .position(|t| t.name == name)
+                .unwrap_or(usize::MAX)
+        };
+        selected.sort_by_key(|s| position(&s.receipt.tool));
+
+        let mut environment = Environment::default();
+        for tool in selected {
+            environment.push(
+                &tool.receipt.tool,
+                &tool.receipt.version,
+                tool.receipt.path_entries,
+                tool.receipt.env.into_iter().chain(tool.extra_env).collect(),
+            );
+        }
+        environment
+    }
+
+    /// Herramientas instaladas sin version activa: la mas nueva con recibo, o la carpeta
+    /// `<root>\<tool>` de instalaciones anteriores a los recibos.
+    fn add_unselected(&mut self, manifest: &Manifest, target_base: &Path) -> Result<(), BeError> {
+        for tool in &manifest.tools {
+            if self.tools.iter().any(|(name, _)| *name == tool.name) {
+                continue;
+            }
+            if let Some(receipt) = Receipt::list_versions(&tool.name)?.pop() {
+                self.push(
+                    &receipt.tool,
+                    &receipt.version,
+                    receipt.path_entries,
+                    receipt.env.into_iter().collect(),
+                );
+            } else if installer::has_legacy_layout(tool, target_base) {
+                self.push_legacy(tool, target_base);
+            }
+        }
+        Ok(())
+    }
+
+    fn push_legacy(&mut self, tool: &Tool, target_base: &Path) {
+        let dir = target_base.join(&tool.name);
+        self.push(
+            &tool.name,
+            &tool.version,
+            tool.resolved_path_entries(&dir),
+            tool.resolved_env(&dir),
+        );
+    }
+
+    fn push(
+        &mut self,
+        tool: &str,
+        version: &str,
+        path_entries: Vec<PathBuf>,
+        vars: Vec<(String, String)>,
+    ) {
+        self.tools.push((tool.to_string(), version.to_string()));
+        self.path_entries.extend(path_entries);
+        self.vars.extend(vars);
+    }
+
+    /// Busca un ejecutable en las carpetas del entorno (con `.exe` en Windows).
+    pub fn find_program(&self, name: &str) -> Option<PathBuf> {
+        let file_name = format!("{}{}", name, env::consts::EXE_SUFFIX);
+        self.path_entries
+            .iter()
+            .map(|dir| dir.join(&file_name))
+            .find(|p| p.is_file())
+    }
+
+    /// Terminal para `be shell`: la PowerShell portable si esta instalada; si no, `pwsh`
+    /// en Windows y la shell del usuario (`$SHELL`) en Linux/macOS.
+    pub fn shell_program(&self) -> String {
+        if let Some(pwsh) = self.find_program("pwsh") {
+            return pwsh.to_string_lossy().to_string();
+        }
+        if cfg!(windows) {
+            return "pwsh".to_string();
+        }
+        env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selected(manifest: &Manifest, name: &str, root: &Path) -> Selected {
+        let tool = manifest.tools.iter().find(|t| t.name == name).unwrap();
+        Selected {
+            receipt: Receipt::new(tool, &root.join(name), Vec::new()),
+            extra_env: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_build_follows_manifest_order_and_keeps_vars() {
+        let manifest = Manifest::default();
+        let root = Path::new("/be");
+        let environment = Environment::build(
+            vec![
+                selected(&manifest, "git", root),
+                selected(&manifest, "mingw64", root),
+                selected(&manifest, "node", root),
+            ],
+            &manifest,
+        );
+        let names: Vec<&str> = environment.tools.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names, vec!["node", "mingw64", "git"]);
+        assert_eq!(environment.path_entries[0], root.join("node"));
+        assert!(environment.vars.iter().any(|(key, _)| key == "CC"));
+    }
+
+    #[test]
+    fn test_legacy_install_is_included() {
+        let manifest = Manifest::default();
+        let node = manifest.tools.iter().find(|t| t.name == "node").unwrap();
+
+        let mut environment = Environment::default();
+        environment.push_legacy(node, Path::new("/be"));
+        assert_eq!(environment.tools[0].0, "node");
+        assert_eq!(
+            environment.path_entries,
+            vec![Path::new("/be").join("node")]
+        );
+        assert_eq!(environment.vars[0].0, "NODE_PATH");
+    }
+}
@@ -23,3 +23,18 @@ pub enum BeError {
     #[error("Operación cancelada por el usuario.")]
     Cancelled,
 }
+
+impl BeError {
+    /// Codigo estable para `--output json`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BeError::Io(_) => "io",
+            BeError::Reqwest(_) => "network",
+            BeError::Zip(_) => "zip",
+            BeError::Json(_) => "json",
+            BeError::Config(_) => "config",
+            BeError::Setup(_) => "setup",
+            BeError::Cancelled => "cancelled",
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use crate::errors::BeError;
+use log::{info, warn};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::StatusCode;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+/// Intentos por peticion (el primero mas los reintentos).
+pub const MAX_ATTEMPTS: u32 = 4;
+/// Espera antes del primer reintento; se duplica en cada uno (1s, 2s, 4s).
+pub const BACKOFF: Duration = Duration::from_secs(1);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Tiempo maximo sin recibir ni enviar datos (no limita la duracion de una descarga).
+const IO_TIMEOUT: Duration = Duration::from_secs(60);
+
+static CLIENT: OnceLock<Client> = OnceLock::new();
+
+/// Cliente HTTP compartido por todo `be`: reutiliza conexiones entre peticiones y se
+/// identifica como `be/<version>`.
+pub fn client() -> &'static Client {
+    CLIENT.get_or_init(|| {
+        Client::builder()
+            .user_agent(concat!("be/", env!("CARGO_PKG_VERSION")))
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(IO_TIMEOUT)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .tcp_keepalive(Duration::from_secs(60))
+            .build()
+            .unwrap_or_else(|e| {
+                warn!(
+                    "No se pudo configurar el cliente HTTP ({}); se usa el de serie.",
+                    e
+                );
+                Client::new()
+            })
+    })
+}
+
+/// Envia la peticion que arma `build` con el cliente compartido, reintentando con espera
+/// exponencial si falla la conexion, vence un timeout o el servidor responde 5xx/429.
+/// Tras el ultimo intento devuelve el error o la respuesta tal cual.
+pub fn send<F>(build: F) -> Result<Response, BeError>
+where
+    F: Fn(&Client) -> RequestBuilder,
+{
+    let mut delay = BACKOFF;
+    let mut attempt = 1;
+    loop {
+        let request = build(client()).build()?;
+        let target = format!("{} {}", request.method(), request.url());
+        info!("{} (intento {}/{})", target, attempt, MAX_ATTEMPTS);
+
+        let result = client().execute(request);
+        let problem = match &result {
+            Ok(response) if is_transient_status(response.status()) => {
+                format!("respuesta {}", response.status())
+            }
+            Err(e) if is_transient_error(e) => e.to_string(),
+            Ok(_) => return Ok(result?),
+            Err(e) => {
+                warn!("{}: {}", target, e);
+                return Ok(result?);
+            }
+        };
+        if attempt == MAX_ATTEMPTS {
+            // Se devuelve la ultima respuesta para que quien llama la informe como siempre
+            warn!("{}: {} (sin mas reintentos)", target, problem);
+            return Ok(result?);
+        }
+        warn!(
+            "{}: {}. Reintentando en {}s...",
+            target,
+            problem,
+            delay.as_secs()
+        );
+        thread::sleep(delay);
+        delay *= 2;
+        attempt += 1;
+    }
+}
+
+fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_timeout() || error.is_connect() || error.is_request() || error.is_body()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transient_statuses() {
+        assert!(is_transient_status(StatusCode::BAD_GATEWAY));
+        assert!(is_transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_transient_status(StatusCode::NOT_FOUND));
+        assert!(!is_transient_status(StatusCode::OK));
+    }
+
+    #[test]
+    fn test_send_retries_server_errors() {
+        use std::io::{Read, Write};
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/tools.json", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            for status in ["503 Service Unavailable", "200 OK"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buffer = [0; 1024];
+                let _ = stream.read(&mut buffer).unwrap();
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok",
+                    status
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        let response = send(|client| client.get(&url)).expect("Deberia reintentar");
+        assert_eq!(response.status(), StatusCode::OK);
+        server.join().unwrap();
+    }
+}
@@ -1,70 +1,254 @@
 use crate::download;
 use crate::errors::BeError;
-use crate::manifest::Tool;
+use crate::manifest::{InstallKind, Tool};
+use crate::output;
+use crate::receipt::{ActiveVersions, Receipt};
 use crate::ui;
+use crate::version;
+use std::cmp::Ordering;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-pub fn install_tool(tool: &Tool, target_base: &Path) -> Result<(), BeError> {
-    ui::print_step(&format!("Instalando {}...", tool.name));
+/// Lo que `be setup` hara con una herramienta al comparar su recibo con el manifiesto.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallAction {
+    Install,
+    Upgrade {
+        from: String,
+    },
+    Downgrade {
+        from: String,
+    },
+    /// Instalacion rota o sin recibo (instalada por una version anterior de `be`).
+    Reinstall,
+    Skip,
+}
+
+impl InstallAction {
+    /// Nombre estable para `--output json`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            InstallAction::Install => "install",
+            InstallAction::Upgrade { .. } => "upgrade",
+            InstallAction::Downgrade { .. } => "downgrade",
+            InstallAction::Reinstall => "reinstall",
+            InstallAction::Skip => "skip",
+        }
+    }
+
+    /// Version activa que se reemplaza, si la hay.
+    pub fn from(&self) -> Option<&str> {
+        match self {
+            InstallAction::Upgrade { from } | InstallAction::Downgrade { from } => Some(from),
+            _ => None,
+        }
+    }
+
+    pub fn describe(&self, tool: &Tool) -> String {
+        match self {
+            InstallAction::Install => format!("{} (nuevo)", tool.version),
+            InstallAction::Upgrade { from } => {
+                format!("{} -> {} (actualizado)", from, tool.version)
+            }
+            InstallAction::Downgrade { from } => {
+                format!("{} -> {} (version anterior)", from, tool.version)
+            }
+            InstallAction::Reinstall => format!("{} (reinstalado)", tool.version),
+            InstallAction::Skip => format!("{} (sin cambios)", tool.version),
+        }
+    }
+}
+
+/// Carpeta de una version concreta: `<root>\<tool>\<version>`.
+pub fn install_dir(target_base: &Path, tool: &Tool) -> PathBuf {
+    target_base.join(&tool.name).join(&tool.version)
+}
+
+/// Instalaciones anteriores a las versiones lado a lado usaban `<root>\<tool>` directamente.
+pub fn has_legacy_layout(tool: &Tool, target_base: &Path) -> bool {
+    target_base.join(&tool.name).join(&tool.check_file).exists()
+}
+
+/// Decide la accion comparando los recibos instalados con la version del manifiesto.
+/// Si la version del manifiesto ya esta instalada no se toca, aunque no sea la activa.
+pub fn plan_install(tool: &Tool, target_base: &Path) -> Result<InstallAction, BeError> {
+    let target_path = install_dir(target_base, tool);
+    // Exe installers write outside of {dir}, so check_file cannot be verified there
+    let files_ok =
+        tool.kind == InstallKind::ExeInstaller || target_path.join(&tool.check_file).exists();
+
+    if Receipt::load(&tool.name, &tool.version)?.is_some() {
+        return Ok(if files_ok {
+            InstallAction::Skip
+        } else {
+            InstallAction::Reinstall
+        });
+    }
+    if has_legacy_layout(tool, target_base) {
+        return Ok(InstallAction::Reinstall);
+    }
+
+    let action = match ActiveVersions::load()?.get(&tool.name) {
+        None => InstallAction::Install,
+        Some(active) => match version::compare(&tool.version, active) {
+            Ordering::Greater => InstallAction::Upgrade {
+                from: active.to_string(),
+            },
+            Ordering::Less => InstallAction::Downgrade {
+                from: active.to_string(),
+            },
+            Ordering::Equal => InstallAction::Reinstall,
+        },
+    };
+    Ok(action)
+}
+
+/// Instala la version del manifiesto en su propia carpeta, sin cambiar la version activa.
+/// Las demas versiones instaladas se conservan para `be use`.
+pub fn install_tool(
+    tool: &Tool,
+    target_base: &Path,
+    action: &InstallAction,
+) -> Result<(), BeError> {
+    if *action == InstallAction::Skip {
+        ui::print_success(&format!("{} {} ya instalado.", tool.name, tool.version));
+        return Ok(());
+    }
+    ui::print_step(&format!(
+        "Instalando {} ({})...",
+        tool.name,
+        action.describe(tool)
+    ));
 
     // 1. Download
-    let zip_name = format!("{}.zip", tool.name);
-    let cached_file = download::ensure_downloaded(&tool.url, &zip_name, tool.sha256.as_deref())?;
+    let cached_file = download::ensure_downloaded(tool)?;
 
-    // 2. Prepare Target
-    let target_path = target_base.join(&tool.name);
+    // 2. Prepare Target (only touch disk once the download succeeded)
+    if has_legacy_layout(tool, target_base) {
+        ui::print_step(&format!(
+            "Migrando instalacion anterior de {} al formato por versiones...",
+            tool.name
+        ));
+        fs::remove_dir_all(target_base.join(&tool.name))?;
+    }
+    let target_path = install_dir(target_base, tool);
+    Receipt::delete(&tool.name, &tool.version)?;
     if target_path.exists() {
-        return Ok(()); // Already installed
+        fs::remove_dir_all(&target_path)?;
+    }
+    let created_dirs: Vec<PathBuf> = tool
+        .resolved_extra_dirs(&target_path)
+        .into_iter()
+        .filter(|dir| !dir.exists())
+        .collect();
+
+    // 3. Install according to the declared strategy
+    match tool.kind {
+        InstallKind::Zip => {
+            install_archive(&cached_file, &target_path, tool, download::extract_zip)?
+        }
+        InstallKind::TarGz => {
+            install_archive(&cached_file, &target_path, tool, download::extract_tar_gz)?
+        }
+        InstallKind::SelfExtracting7z => install_self_extracting(&cached_file, &target_path, tool)?,
+        InstallKind::ExeInstaller => install_exe(&cached_file, &target_path, tool)?,
+        InstallKind::SingleBinary => install_single_binary(&cached_file, &target_path, tool)?,
     }
 
-    // 3. Extract logic based on tool type
-    match tool.name.as_str() {
-        // Rustup is an EXE installer, not a ZIP
-        "rustup" => install_rust(&cached_file, target_base)?,
-        // Git Portable is a self-extracting EXE (7z)
-        "git" => install_git_portable(&cached_file, &target_path)?,
-        // VSCodium is a ZIP
-        "vscodium" => install_vscodium(&cached_file, &target_path)?,
-        // Standard ZIPs
-        _ => install_generic_zip(&cached_file, &target_path)?,
+    create_marker_dirs(tool, &target_path)?;
+
+    // 4. Receipt
+    Receipt::new(tool, &target_path, created_dirs).save()?;
+
+    ui::print_success(&format!("{} {} instalado.", tool.name, tool.version));
+    Ok(())
+}
+
+/// Marca la version del manifiesto como activa tras `install_tool`.
+/// Si no hubo cambios se respeta la version elegida con `be use`.
+pub fn activate(tool: &Tool, action: &InstallAction) -> Result<(), BeError> {
+    let mut active = ActiveVersions::load()?;
+    if *action == InstallAction::Skip && active.get(&tool.name).is_some() {
+        return Ok(());
     }
+    active.set(&tool.name, &tool.version);
+    active.save()?;
 
-    ui::print_success(&format!("{} instalado.", tool.name));
+    if let InstallAction::Upgrade { from } | InstallAction::Downgrade { from } = action {
+        ui::print_step(&format!(
+            "La version {} sigue disponible con 'be use {} {}'.",
+            from, tool.name, from
+        ));
+    }
     Ok(())
 }
 
-fn install_generic_zip(source: &Path, target: &Path) -> Result<(), BeError> {
-    let temp_extract =
-        std::env::temp_dir().join(format!("brisas_extract_{}", uuid::Uuid::new_v4()));
+fn install_archive(
+    source: &Path,
+    target: &Path,
+    tool: &Tool,
+    extract: fn(&Path, &Path) -> Result<(), BeError>,
+) -> Result<(), BeError> {
+    // Extract next to the target so the result can be moved instead of copied: a copy
+    // would follow the symlinks of Linux tarballs (e.g. node's bin/npm)
+    let parent = target
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir);
+    fs::create_dir_all(&parent)?;
+    let temp_extract = parent.join(format!(".brisas_extract_{}", uuid::Uuid::new_v4()));
     if temp_extract.exists() {
         let _ = fs::remove_dir_all(&temp_extract);
     }
 
-    download::extract_zip(source, &temp_extract)?;
+    // The temp dir lives inside the tools root: remove it whether or not the install worked
+    let result = extract_and_place(source, &temp_extract, target, tool, extract);
+    let _ = fs::remove_dir_all(&temp_extract);
+    result
+}
+
+fn extract_and_place(
+    source: &Path,
+    temp_extract: &Path,
+    target: &Path,
+    tool: &Tool,
+    extract: fn(&Path, &Path) -> Result<(), BeError>,
+) -> Result<(), BeError> {
+    extract(source, temp_extract)?;
 
-    let mut final_source = temp_extract.clone();
-    // Verify if it contains a single folder wrapper
-    if let Ok(entries) = fs::read_dir(&temp_extract) {
-        let items: Vec<_> = entries.filter_map(Result::ok).collect();
-        if items.len() == 1 && items[0].path().is_dir() {
-            final_source = items[0].path();
+    let final_source = match &tool.strip_prefix {
+        Some(prefix) => {
+            let dir = temp_extract.join(prefix);
+            if !dir.is_dir() {
+                return Err(BeError::Setup(format!(
+                    "El archivo de {} no contiene la carpeta '{}'.",
+                    tool.name, prefix
+                )));
+            }
+            dir
         }
-    }
+        None => single_wrapper_dir(temp_extract).unwrap_or_else(|| temp_extract.to_path_buf()),
+    };
 
-    copy_dir_with_progress(&final_source, target)?;
-    let _ = fs::remove_dir_all(&temp_extract);
+    if fs::rename(&final_source, target).is_err() {
+        copy_dir_with_progress(&final_source, target)?;
+    }
     Ok(())
 }
 
-fn install_git_portable(source: &Path, target: &Path) -> Result<(), BeError> {
-    // Git Portable is an EXE but acts like a self-extracting archive.
-    // However, 7z.exe args are tricky.
-    // Simpler approach: It's actually a 7z archive with an SFX header.
-    // We can try to unzip it if our zip lib supports it, likely NOT.
-    // Better: Run it with `-y -gm2 -nr -o"Target"` (7-zip args)
+/// Si `dir` contiene una unica carpeta (envoltorio tipico de los ZIP), la devuelve.
+fn single_wrapper_dir(dir: &Path) -> Option<PathBuf> {
+    let items: Vec<_> = fs::read_dir(dir).ok()?.filter_map(Result::ok).collect();
+    if items.len() == 1 && items[0].path().is_dir() {
+        Some(items[0].path())
+    } else {
+        None
+    }
+}
 
-    ui::print_step("Descomprimiendo Git Portable...");
+fn install_self_extracting(source: &Path, target: &Path, tool: &Tool) -> Result<(), BeError> {
+    // 7-Zip SFX archives (e.g. PortableGit) extract themselves with `-y -o"Target"`
+    ui::print_step(&format!("Descomprimiendo {}...", tool.name));
     if !target.exists() {
         fs::create_dir_all(target)?;
     }
@@ -72,50 +256,66 @@ fn install_git_portable(source: &Path, target: &Path) -> Result<(), BeError> {
     let status = std::process::Command::new(source)
         .arg("-y")
         .arg(format!("-o{}", target.display()))
+        .stdout(output::child_stdout())
         .status()
-        .map_err(|e| BeError::Setup(format!("Fallo descomprimiendo Git: {}", e)))?;
+        .map_err(|e| BeError::Setup(format!("Fallo descomprimiendo {}: {}", tool.name, e)))?;
 
     if !status.success() {
-        return Err(BeError::Setup(
-            "Git Portable fallo al descomprimirse.".into(),
-        ));
+        return Err(BeError::Setup(format!(
+            "{} fallo al descomprimirse.",
+            tool.name
+        )));
     }
 
     Ok(())
 }
 
-fn install_vscodium(source: &Path, target: &Path) -> Result<(), BeError> {
-    install_generic_zip(source, target)?;
+fn install_exe(source: &Path, target: &Path, tool: &Tool) -> Result<(), BeError> {
+    ui::print_step(&format!("Ejecutando instalador de {}...", tool.name));
 
-    // Make Portable
-    ui::print_step("Haciendo VSCodium Portable...");
-    let data_dir = target.join("data");
-    if !data_dir.exists() {
-        fs::create_dir(&data_dir)?;
-    }
-    Ok(())
-}
-
-fn install_rust(source: &Path, _target_base: &Path) -> Result<(), BeError> {
-    ui::print_step("Ejecutando Instalador de Rust (rustup-init)...");
-
-    // rustup-init.exe -y --default-host x86_64-pc-windows-gnu --default-toolchain stable --no-modify-path
     let status = std::process::Command::new(source)
-        .arg("-y")
-        .arg("--default-host")
-        .arg("x86_64-pc-windows-gnu")
-        .arg("--default-toolchain")
-        .arg("stable")
-        .arg("--no-modify-path")
+        .args(tool.resolved_installer_args(target))
+        .stdout(output::child_stdout())
         .status()
-        .map_err(|e| BeError::Setup(format!("Fallo ejecutando rustup-init: {}", e)))?;
+        .map_err(|e| {
+            BeError::Setup(format!(
+                "Fallo ejecutando instalador de {}: {}",
+                tool.name, e
+            ))
+        })?;
 
     if !status.success() {
-        return Err(BeError::Setup(
-            "rustup-init fallo (posiblemente falta internet).".into(),
-        ));
+        return Err(BeError::Setup(format!(
+            "El instalador de {} fallo (posiblemente falta internet).",
+            tool.name
+        )));
+    }
+
+    Ok(())
+}
+
+fn install_single_binary(source: &Path, target: &Path, tool: &Tool) -> Result<(), BeError> {
+    let dest = target.join(&tool.check_file);
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
     }
+    fs::copy(source, &dest)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&dest, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
 
+fn create_marker_dirs(tool: &Tool, target: &Path) -> Result<(), BeError> {
+    // e.g. VSCodium switches to portable mode when `data/` exists
+    for marker in &tool.marker_dirs {
+        let dir = target.join(marker);
+        if !dir.exists() {
+            fs::create_dir_all(&dir)?;
+        }
+    }
     Ok(())
 }
 
@@ -133,3 +333,36 @@ fn copy_dir_with_progress(src: &Path, dst: &Path) -> Result<(), BeError> {
         .map_err(|e| BeError::Setup(format!("Error copiando archivos: {}", e)))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest::Manifest;
+
+    fn partial_extract(_source: &Path, dest: &Path) -> Result<(), BeError> {
+        fs::create_dir_all(dest)?;
+        fs::write(dest.join("a.txt"), b"a")?;
+        Err(BeError::Setup("archivo corrupto".into()))
+    }
+
+    fn flat_extract(_source: &Path, dest: &Path) -> Result<(), BeError> {
+        fs::create_dir_all(dest)?;
+        fs::write(dest.join("a.txt"), b"a")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_install_archive_removes_temp_dir_on_error() {
+        let root = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let target = root.path().join("node").join("22.12.0");
+        let mut tool = Manifest::default().tools[0].clone();
+
+        assert!(install_archive(Path::new("x.zip"), &target, &tool, partial_extract).is_err());
+        tool.strip_prefix = Some("no-existe".into());
+        assert!(install_archive(Path::new("x.zip"), &target, &tool, flat_extract).is_err());
+
+        let leftovers: Vec<_> = fs::read_dir(root.path().join("node")).unwrap().collect();
+        assert!(leftovers.is_empty());
+        assert!(!target.exists());
+    }
+}
@@ -1,15 +1,11 @@
+use crate::config;
 use log::LevelFilter;
 use simplelog::{CombinedLogger, Config, WriteLogger};
-use std::env;
 use std::fs::{self, File};
-use std::path::PathBuf;
 
+/// `be.log` vive en `config::state_dir()` junto a los recibos, asi `be clean` lo borra.
 pub fn init() -> Result<(), Box<dyn std::error::Error>> {
-    let local_app_data = match env::var("LOCALAPPDATA") {
-        Ok(val) => val,
-        Err(_) => ".".to_string(),
-    };
-    let log_dir = PathBuf::from(&local_app_data).join("BrisasEnv");
+    let log_dir = config::state_dir()?;
 
     if !log_dir.exists() {
         fs::create_dir_all(&log_dir)?;
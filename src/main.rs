@@ -1,18 +1,31 @@
+mod activate;
 mod admin; // New module
+mod cache;
 mod cli;
 mod config;
+mod doctor;
 mod download;
+mod env_store;
+mod environment;
 mod errors;
+mod http;
 mod installer;
 mod logger;
 mod manifest;
+mod output;
+mod path_history;
+mod path_list;
+mod plan;
+mod project;
+mod receipt;
 mod run;
 pub mod setup;
 mod ui;
+mod version;
 
 use clap::Parser;
-use cli::{Cli, Commands};
-use config::get_env_config; // Fixed import
+use cli::{CacheAction, Cli, Commands, OutputFormat, PathAction};
+use environment::Environment;
 use inquire::Select;
 use log::{error, info};
 use run::run_command;
@@ -29,15 +42,34 @@ fn main() {
     }
 
     let cli = Cli::parse();
+    output::set_json(cli.output == OutputFormat::Json);
 
-    if let Err(e) = execute_command(&cli) {
-        error!("Error Fatal: {}", e);
-        eprintln!("\nError Fatal: {}", e);
-        std::process::exit(1);
+    match execute_command(&cli) {
+        Ok(0) => {}
+        // `be run` / `be shell` exit with the child's code so CI scripts see failures
+        Ok(code) => std::process::exit(code),
+        Err(e) => {
+            error!("Error Fatal: {}", e);
+            if output::is_json() {
+                let _ = output::emit_error(cli.command.name(), &e);
+            } else {
+                eprintln!("\nError Fatal: {}", e);
+            }
+            std::process::exit(1);
+        }
     }
 }
 
-fn execute_command(cli: &Cli) -> Result<(), errors::BeError> {
+/// Ejecuta el subcomando y devuelve el codigo de salida de `be`.
+fn execute_command(cli: &Cli) -> Result<i32, errors::BeError> {
+    let name = cli.command.name();
+    if output::is_json() && !cli.command.supports_json() {
+        return Err(errors::BeError::Config(format!(
+            "--output json no esta disponible para '{}'. Usalo con status, doctor, setup, \
+             clean, manifest-gen, cache list o cache verify.",
+            name
+        )));
+    }
     match &cli.command {
         Commands::Run { args } => {
             if args.is_empty() {
@@ -45,43 +77,77 @@ fn execute_command(cli: &Cli) -> Result<(), errors::BeError> {
                     "No se proporcionó ningún comando.".into(),
                 ));
             }
-            let config = get_env_config()?;
-            run_command(&config, &args[0], &args[1..]);
+            let environment = Environment::load()?;
+            return run_command(&environment, &args[0], &args[1..]);
         }
-        Commands::Shell => {
-            let config = get_env_config()?;
-            let mut shell = "pwsh".to_string();
-
-            // Try to find pwsh relative to LocalAppData
-            if let Ok(local) = std::env::var("LOCALAPPDATA") {
-                let pwsh_path = std::path::PathBuf::from(local)
-                    .join("pwsh")
-                    .join("pwsh.exe");
-                if pwsh_path.exists() {
-                    shell = pwsh_path.to_string_lossy().to_string();
-                }
-            }
-
-            println!("Iniciando terminal portable ({})", shell);
-            run_command(&config, &shell, &[]);
+        Commands::Shell => return start_shell(),
+        Commands::Env { shell } => {
+            activate::print_script(*shell)?;
+        }
+        Commands::Setup {
+            tools,
+            all,
+            profile,
+            yes,
+            dry_run,
+        } => {
+            let report = setup::setup_system(&setup::SetupOptions {
+                tools: tools.clone(),
+                all: *all,
+                profile: profile.clone(),
+                yes: *yes,
+                dry_run: *dry_run,
+            })?;
+            output::emit(name, &report)?;
+        }
+        Commands::Use { tool, version } => {
+            setup::use_version(tool, version.as_deref())?;
         }
-        Commands::Setup => {
-            setup::setup_system()?;
+        Commands::WhichVersion { tool } => {
+            project::explain(tool.as_deref())?;
         }
-        Commands::Clean => {
-            setup::clean_system()?;
+        Commands::Clean { tool, dry_run } => {
+            let report = setup::clean_system(tool.as_deref(), *dry_run)?;
+            output::emit(name, &report)?;
         }
         Commands::Status => {
-            setup::check_status();
+            output::emit(name, &doctor::run(false)?)?;
         }
+        Commands::Doctor => {
+            output::emit(name, &doctor::run(true)?)?;
+        }
+        Commands::Path { action } => match action {
+            PathAction::History => path_history::print_history()?,
+            PathAction::Restore { id } => path_history::restore(id.as_deref())?,
+        },
+        Commands::Cache { action } => match action {
+            CacheAction::List => output::emit(name, &cache::list()?)?,
+            CacheAction::Verify => output::emit(name, &cache::verify()?)?,
+            CacheAction::Prune { keep_latest } => cache::prune(*keep_latest)?,
+            CacheAction::Import { file } => cache::import(file)?,
+            CacheAction::Export { dir } => cache::export(dir)?,
+        },
         Commands::Help => {
             print_help();
         }
         Commands::ManifestGen => {
-            admin::generate_manifest()?;
+            if output::is_json() {
+                output::emit(name, &admin::check_manifest()?)?;
+            } else {
+                admin::generate_manifest()?;
+            }
         }
     }
-    Ok(())
+    Ok(0)
+}
+
+/// `be shell` y el menu: abre la terminal con todas las herramientas instaladas.
+fn start_shell() -> Result<i32, errors::BeError> {
+    let environment = Environment::load()?;
+    // Prefer the portable pwsh of the environment (active or pinned version)
+    let shell = environment.shell_program();
+    println!("Iniciando terminal portable ({})", shell);
+    run_command(&environment, &shell, &[])
 }
 
 fn print_help() {
@@ -94,20 +160,51 @@ fn print_help() {
         "  setup             -> DESCARGA E INSTALA automaticamente Node.js, GCC y PowerShell."
     );
     println!("                       Tambien anade estas herramientas a tu PATH (temporalmente o en registro).");
-    println!("  clean             -> DESINSTALADOR COMPLETO. Borra las carpetas descargadas y");
-    println!("                       limpia cualquier rastro dejado en el Registro de Windows.");
+    println!("                       Sin preguntas: 'be setup --tools node,git', '--all', '--yes'");
+    println!("                       o '--profile <nombre>' (perfiles definidos en tools.json).");
+    println!("                       '--dry-run' muestra descargas, carpetas y PATH sin instalar.");
+    println!(
+        "  clean [tool]      -> DESINSTALADOR COMPLETO. Borra carpetas, accesos directos, cache,"
+    );
+    println!(
+        "                       logs y el rastro dejado en el Registro (PATH) segun lo instalado."
+    );
+    println!("                       Con herramienta solo quita esa: 'be clean git'.");
+    println!("                       'be clean --dry-run' muestra lo que se borraria.");
     println!(
         "  status            -> DIAGNOSTICO. Te dice si falta algo y si las variables de entorno"
     );
     println!("                       estan bien configuradas.");
+    println!("  doctor            -> Por herramienta: version esperada y real, si se ejecuta la");
+    println!("                       portable o una global que la tapa, y como arreglarlo.");
     println!(
         "  shell             -> Abre una nueva terminal (PowerShell) con todas las herramientas"
     );
     println!("                       cargadas y listas para usar.");
+    println!("  env --shell <sh>  -> Imprime un script que carga el entorno en la terminal actual");
+    println!("                       (pwsh, cmd, bash o fish); 'deactivate' lo deshace.");
+    println!("  use <tool> [ver]  -> Cambia la version activa de una herramienta instalada.");
+    println!("                       Ejemplo: 'be use node 18' (sin version lista las instaladas)");
+    println!(
+        "  which-version     -> Explica que version usara 'run' en esta carpeta y que archivo"
+    );
+    println!(
+        "                       la pidio (.brisas.toml, .nvmrc, .tool-versions, rust-toolchain)."
+    );
+    println!("  path history      -> Lista las copias del PATH guardadas antes de cada cambio.");
+    println!("  path restore [id] -> Vuelve el PATH a una copia (sin id, la mas reciente).");
+    println!("  cache list        -> Descargas guardadas: herramienta, version, tamano y hash.");
+    println!("  cache verify      -> Recalcula los hashes y elimina las descargas danadas.");
+    println!("  cache prune       -> Vacia la cache ('--keep-latest' conserva la ultima version).");
+    println!("  cache import <f>  -> Agrega un archivo (ej. de un USB) si su hash esta en el");
+    println!("                       manifiesto. 'cache export <carpeta>' copia la cache.");
     println!("  run <cmd>         -> Ejecuta un comando suelto dentro del entorno 'magico'.");
     println!("                       Ejemplo: 'be run npm start'");
     println!("  help              -> Muestra esta pantalla de ayuda.");
     println!();
+    println!("Con '--output json' status, doctor, setup, clean, manifest-gen y cache list/verify");
+    println!("imprimen solo un JSON (resultado o error con codigo) para usarlos desde scripts.");
+    println!();
     println!("TRUCO: Si ejecutas 'be.exe' (doble click) sin comandos, veras un MENU INTERACTIVO.");
 }
 
@@ -133,19 +230,16 @@ fn interactive_menu() {
         match ans {
             Ok(choice) => {
                 let result = match choice {
-                    "Iniciar Shell Portable" => get_env_config().map(|config| {
-                        run_command(&config, "pwsh", &[]);
-                    }),
-                    "Instalar / Reparar (Setup)" => setup::setup_system(),
-                    "Verificar Estado (Status)" => {
-                        setup::check_status();
-                        Ok(())
+                    "Iniciar Shell Portable" => start_shell().map(|_| ()),
+                    "Instalar / Reparar (Setup)" => {
+                        setup::setup_system(&setup::SetupOptions::default()).map(|_| ())
                     }
+                    "Verificar Estado (Status)" => doctor::run(true).map(|_| ()),
                     "Ayuda / Que es esto?" => {
                         print_help();
                         Ok(())
                     }
-                    "Desinstalar (Clean)" => setup::clean_system(),
+                    "Desinstalar (Clean)" => setup::clean_system(None, false).map(|_| ()),
                     "Administracion (Manifest Gen)" => admin::generate_manifest(),
                     "Salir" => break,
                     _ => Ok(()),
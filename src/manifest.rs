@@ -1,7 +1,28 @@
 use crate::errors::BeError;
+use crate::http;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Estrategia con la que se instala el archivo descargado.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum InstallKind {
+    /// Archivo ZIP que se extrae en la carpeta de la herramienta.
+    #[default]
+    Zip,
+    /// EXE autoextraible de 7-Zip (ej. PortableGit), se ejecuta con `-y -o<destino>`.
+    #[serde(rename = "self-extracting-7z")]
+    SelfExtracting7z,
+    /// Instalador EXE que se ejecuta con `installer_args`.
+    ExeInstaller,
+    /// Archivo `.tar.gz` que se extrae en la carpeta de la herramienta.
+    #[serde(alias = "tar.gz")]
+    TarGz,
+    /// Un unico ejecutable que se copia como `check_file`.
+    SingleBinary,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Tool {
@@ -10,11 +31,113 @@ pub struct Tool {
     pub url: String,
     pub check_file: String,
     pub sha256: Option<String>,
+    /// Carpetas que se agregan al PATH. Admiten los marcadores `{dir}` (carpeta de
+    /// instalacion de la herramienta) y `{home}` (carpeta del usuario).
+    #[serde(default)]
+    pub path_entries: Vec<String>,
+    /// Variables de entorno para `be run` / `be shell`. Mismos marcadores que `path_entries`.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub kind: InstallKind,
+    /// Argumentos para `exe-installer`. Mismos marcadores que `path_entries`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub installer_args: Vec<String>,
+    /// Subcarpeta del archivo que se usa como raiz. Si se omite y el archivo trae
+    /// una unica carpeta envolvente, esta se quita automaticamente.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strip_prefix: Option<String>,
+    /// Carpetas que se crean tras instalar (ej. `data` para VSCodium portable).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub marker_dirs: Vec<String>,
+    /// Carpetas fuera de `{dir}` donde escribe el instalador (ej. `{home}/.cargo`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_dirs: Vec<String>,
+    /// Comando que usa `be doctor` para verificar la herramienta (ej. `node --version`).
+    /// Sin valor se usa `<check_file sin extension> --version`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check_command: Option<String>,
+    /// Expresion regular que extrae la version de la salida de `check_command`
+    /// (el primer grupo, o todo el texto encontrado si no tiene grupos).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version_regex: Option<String>,
+}
+
+impl Tool {
+    /// Resuelve `path_entries` contra la carpeta de instalacion.
+    pub fn resolved_path_entries(&self, install_dir: &Path) -> Vec<PathBuf> {
+        self.path_entries
+            .iter()
+            .map(|entry| expand_placeholders(entry, install_dir))
+            .collect()
+    }
+
+    /// Resuelve las variables de `env`. Los valores sin marcadores se copian tal cual.
+    pub fn resolved_env(&self, install_dir: &Path) -> Vec<(String, String)> {
+        self.env
+            .iter()
+            .map(|(key, value)| (key.clone(), expand_value(value, install_dir)))
+            .collect()
+    }
+
+    /// Resuelve `extra_dirs` igual que `path_entries`.
+    pub fn resolved_extra_dirs(&self, install_dir: &Path) -> Vec<PathBuf> {
+        self.extra_dirs
+            .iter()
+            .map(|dir| expand_placeholders(dir, install_dir))
+            .collect()
+    }
+
+    /// Resuelve `installer_args` igual que los valores de `env`.
+    pub fn resolved_installer_args(&self, install_dir: &Path) -> Vec<String> {
+        self.installer_args
+            .iter()
+            .map(|arg| expand_value(arg, install_dir))
+            .collect()
+    }
+}
+
+/// Expande los marcadores de un valor de `env` o `installer_args`. Si el valor es una ruta
+/// (empieza con el unico marcador, ej. `{dir}/bin/gcc.exe`) se usa el separador del sistema;
+/// si no (ej. `/DIR={dir}`, `x;{dir}\bin`) los marcadores se reemplazan como texto.
+fn expand_value(value: &str, install_dir: &Path) -> String {
+    let placeholders = value.matches("{dir}").count() + value.matches("{home}").count();
+    let is_path = placeholders == 1 && (value.starts_with("{dir}") || value.starts_with("{home}"));
+    if is_path {
+        return expand_placeholders(value, install_dir)
+            .to_string_lossy()
+            .to_string();
+    }
+    let home = dirs::home_dir().unwrap_or_default();
+    value
+        .replace("{dir}", &install_dir.to_string_lossy())
+        .replace("{home}", &home.to_string_lossy())
+}
+
+/// Convierte una plantilla como `{dir}/bin` en una ruta real usando el separador del sistema.
+/// Las rutas relativas (sin marcador) se interpretan dentro de `install_dir`.
+fn expand_placeholders(template: &str, install_dir: &Path) -> PathBuf {
+    let mut parts = template.split(['/', '\\']);
+    let mut resolved = match parts.next() {
+        Some("{dir}") | Some("") | Some(".") | None => install_dir.to_path_buf(),
+        Some("{home}") => dirs::home_dir().unwrap_or_default(),
+        Some(first) => install_dir.join(first),
+    };
+    for part in parts.filter(|p| !p.is_empty()) {
+        resolved.push(part);
+    }
+    resolved
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Manifest {
     pub tools: Vec<Tool>,
+    /// Conjuntos de herramientas por curso (ej. "web", "c-course") para `be setup --profile`.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Vec<String>>,
+    /// Perfil preseleccionado en `be setup`. Sin perfil se preselecciona todo el manifiesto.
+    #[serde(default)]
+    pub default_profile: Option<String>,
 }
 
 impl Manifest {
@@ -27,6 +150,15 @@ impl Manifest {
                     url: "https://nodejs.org/dist/v22.12.0/node-v22.12.0-win-x64.zip".into(),
                     check_file: "node.exe".into(),
                     sha256: None,
+                    path_entries: vec!["{dir}".into()],
+                    env: env_map(&[("NODE_PATH", "{dir}/node_modules")]),
+                    kind: InstallKind::Zip,
+                    installer_args: Vec::new(),
+                    strip_prefix: None,
+                    marker_dirs: Vec::new(),
+                    extra_dirs: Vec::new(),
+                    check_command: Some("node --version".into()),
+                    version_regex: Some("v(\\d+\\.\\d+\\.\\d+)".into()),
                 },
                 Tool {
                     name: "mingw64".into(),
@@ -34,6 +166,15 @@ impl Manifest {
                     url: "https://github.com/brechtsanders/winlibs_mingw/releases/download/14.2.0posix-19.1.1-12.0.0-ucrt-r2/winlibs-x86_64-posix-seh-gcc-14.2.0-llvm-19.1.1-mingw-w64ucrt-12.0.0-r2.zip".into(),
                     check_file: "bin/gcc.exe".into(),
                     sha256: None,
+                    path_entries: vec!["{dir}/bin".into()],
+                    env: env_map(&[("CC", "{dir}/bin/gcc.exe"), ("CXX", "{dir}/bin/g++.exe")]),
+                    kind: InstallKind::Zip,
+                    installer_args: Vec::new(),
+                    strip_prefix: None,
+                    marker_dirs: Vec::new(),
+                    extra_dirs: Vec::new(),
+                    check_command: Some("gcc --version".into()),
+                    version_regex: Some("\\) (\\d+\\.\\d+\\.\\d+)".into()),
                 },
                 Tool {
                     name: "pwsh".into(),
@@ -41,6 +182,15 @@ impl Manifest {
                     url: "https://github.com/PowerShell/PowerShell/releases/download/v7.5.4/PowerShell-7.5.4-win-x64.zip".into(),
                     check_file: "pwsh.exe".into(),
                     sha256: None,
+                    path_entries: vec!["{dir}".into()],
+                    env: BTreeMap::new(),
+                    kind: InstallKind::Zip,
+                    installer_args: Vec::new(),
+                    strip_prefix: None,
+                    marker_dirs: Vec::new(),
+                    extra_dirs: Vec::new(),
+                    check_command: Some("pwsh --version".into()),
+                    version_regex: Some("PowerShell (\\S+)".into()),
                 },
                 Tool {
                     name: "git".into(),
@@ -48,6 +198,15 @@ impl Manifest {
                     url: "https://github.com/git-for-windows/git/releases/download/v2.47.1.windows.1/PortableGit-2.47.1-64-bit.7z.exe".into(),
                     check_file: "bin/git.exe".into(),
                     sha256: None,
+                    path_entries: vec!["{dir}/bin".into(), "{dir}/cmd".into()],
+                    env: BTreeMap::new(),
+                    kind: InstallKind::SelfExtracting7z,
+                    installer_args: Vec::new(),
+                    strip_prefix: None,
+                    marker_dirs: Vec::new(),
+                    extra_dirs: Vec::new(),
+                    check_command: Some("git --version".into()),
+                    version_regex: Some("git version (\\d+\\.\\d+\\.\\d+)".into()),
                 },
                 Tool {
                     name: "vscodium".into(),
@@ -55,6 +214,15 @@ impl Manifest {
                     url: "https://github.com/VSCodium/vscodium/releases/download/1.96.0.24351/VSCodium-win32-x64-1.96.0.24351.zip".into(),
                     check_file: "VSCodium.exe".into(),
                     sha256: None,
+                    path_entries: vec!["{dir}/bin".into()],
+                    env: BTreeMap::new(),
+                    kind: InstallKind::Zip,
+                    installer_args: Vec::new(),
+                    strip_prefix: None,
+                    marker_dirs: vec!["data".into()],
+                    extra_dirs: Vec::new(),
+                    check_command: Some("codium --version".into()),
+                    version_regex: Some("(?m)^(\\d+\\.\\d+\\.\\d+)".into()),
                 },
                 Tool {
                     name: "rustup".into(),
@@ -62,15 +230,90 @@ impl Manifest {
                     url: "https://win.rustup.rs/x86_64".into(),
                     check_file: "rustup-init.exe".into(),
                     sha256: None,
+                    path_entries: vec!["{home}/.cargo/bin".into()],
+                    env: BTreeMap::new(),
+                    kind: InstallKind::ExeInstaller,
+                    installer_args: [
+                        "-y",
+                        "--default-host",
+                        "x86_64-pc-windows-gnu",
+                        "--default-toolchain",
+                        "stable",
+                        "--no-modify-path",
+                    ]
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect(),
+                    strip_prefix: None,
+                    marker_dirs: Vec::new(),
+                    extra_dirs: vec!["{home}/.cargo".into(), "{home}/.rustup".into()],
+                    check_command: Some("rustup --version".into()),
+                    version_regex: Some("rustup (\\d+\\.\\d+\\.\\d+)".into()),
                 },
-
             ],
+            profiles: [
+                ("standard", &["node", "mingw64", "pwsh", "git", "vscodium"][..]),
+                ("web", &["node", "git", "vscodium", "pwsh"][..]),
+                ("c-course", &["mingw64", "git", "vscodium", "pwsh"][..]),
+                (
+                    "full",
+                    &["node", "mingw64", "pwsh", "git", "vscodium", "rustup"][..],
+                ),
+            ]
+            .iter()
+            .map(|(name, tools)| {
+                (
+                    name.to_string(),
+                    tools.iter().map(|t| t.to_string()).collect(),
+                )
+            })
+            .collect(),
+            default_profile: Some("standard".into()),
+        }
+    }
+
+    /// Herramientas de un perfil, validando que existan en el manifiesto.
+    pub fn profile_tools(&self, profile: &str) -> Result<Vec<String>, BeError> {
+        let tools = self.profiles.get(profile).ok_or_else(|| {
+            let available: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            BeError::Config(format!(
+                "Perfil desconocido: {}. Disponibles: {}",
+                profile,
+                available.join(", ")
+            ))
+        })?;
+        for name in tools {
+            if !self.tools.iter().any(|t| t.name == *name) {
+                return Err(BeError::Config(format!(
+                    "El perfil {} menciona una herramienta que no esta en el manifiesto: {}",
+                    profile, name
+                )));
+            }
+        }
+        Ok(tools.clone())
+    }
+
+    /// Herramientas del perfil por defecto, o todo el manifiesto si no hay uno.
+    pub fn default_tools(&self) -> Result<Vec<String>, BeError> {
+        match &self.default_profile {
+            Some(profile) => self.profile_tools(profile),
+            None => Ok(self.tools.iter().map(|t| t.name.clone()).collect()),
+        }
+    }
+
+    /// Carga `tools.json` del directorio actual o, si no existe, el manifiesto por defecto.
+    pub fn load_local_or_default() -> Result<Self, BeError> {
+        let manifest_path = Path::new("tools.json");
+        if manifest_path.exists() {
+            Self::load_from_file(manifest_path)
+        } else {
+            Ok(Self::default())
         }
     }
 
     #[allow(dead_code)]
     pub fn load_from_url(url: &str) -> Result<Self, BeError> {
-        let resp = reqwest::blocking::get(url)?;
+        let resp = http::send(|client| client.get(url))?;
         if let Err(e) = resp.error_for_status_ref() {
             return Err(BeError::Reqwest(e));
         }
@@ -92,6 +335,13 @@ impl Manifest {
     }
 }
 
+fn env_map(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,7 +363,120 @@ mod tests {
 
         let manifest: Manifest = serde_json::from_str(json).expect("Deberia parsear correctamente");
         assert_eq!(manifest.tools.len(), 1);
+        assert!(manifest.profiles.is_empty());
         assert_eq!(manifest.tools[0].name, "test_tool");
         assert_eq!(manifest.tools[0].check_file, "bin/tool.exe");
+        assert!(manifest.tools[0].path_entries.is_empty());
+        assert!(manifest.tools[0].env.is_empty());
+        assert_eq!(manifest.tools[0].kind, InstallKind::Zip);
+    }
+
+    #[test]
+    fn test_profiles() {
+        let manifest = Manifest::default();
+        assert_eq!(
+            manifest.default_tools().expect("Perfil por defecto valido"),
+            vec!["node", "mingw64", "pwsh", "git", "vscodium"]
+        );
+        assert_eq!(
+            manifest.profile_tools("web").expect("Perfil web"),
+            vec!["node", "git", "vscodium", "pwsh"]
+        );
+        assert!(manifest.profile_tools("noexiste").is_err());
+
+        let mut broken = Manifest::default();
+        broken
+            .profiles
+            .insert("roto".into(), vec!["node".into(), "python".into()]);
+        assert!(broken.profile_tools("roto").is_err());
+
+        broken.default_profile = None;
+        assert_eq!(
+            broken.default_tools().expect("Sin perfil usa todo").len(),
+            broken.tools.len()
+        );
+    }
+
+    #[test]
+    fn test_parse_install_kinds() {
+        for (raw, expected) in [
+            ("zip", InstallKind::Zip),
+            ("self-extracting-7z", InstallKind::SelfExtracting7z),
+            ("exe-installer", InstallKind::ExeInstaller),
+            ("tar-gz", InstallKind::TarGz),
+            ("tar.gz", InstallKind::TarGz),
+            ("single-binary", InstallKind::SingleBinary),
+        ] {
+            let kind: InstallKind =
+                serde_json::from_str(&format!("\"{}\"", raw)).expect("Tipo valido");
+            assert_eq!(kind, expected);
+        }
+    }
+
+    #[test]
+    fn test_resolve_path_entries_and_env() {
+        let json = r#"
+        {
+            "name": "mingw64",
+            "version": "14.2.0",
+            "url": "http://example.com/mingw.zip",
+            "check_file": "bin/gcc.exe",
+            "path_entries": ["{dir}/bin", "lib"],
+            "env": { "CC": "{dir}/bin/gcc.exe", "MODE": "portable" }
+        }
+        "#;
+
+        let tool: Tool = serde_json::from_str(json).expect("Deberia parsear la herramienta");
+        let dir = Path::new("base").join("mingw64");
+
+        assert_eq!(
+            tool.resolved_path_entries(&dir),
+            vec![dir.join("bin"), dir.join("lib")]
+        );
+        assert_eq!(
+            tool.resolved_env(&dir),
+            vec![
+                (
+                    "CC".to_string(),
+                    dir.join("bin")
+                        .join("gcc.exe")
+                        .to_string_lossy()
+                        .to_string()
+                ),
+                ("MODE".to_string(), "portable".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_embedded_placeholders_are_replaced_in_place() {
+        let json = r#"
+        {
+            "name": "pwsh",
+            "version": "7.5.4",
+            "url": "http://example.com/pwsh.msi",
+            "check_file": "pwsh.exe",
+            "kind": "exe-installer",
+            "installer_args": ["/S", "/DIR={dir}", "--prefix={dir}"],
+            "env": { "FOO": "x;{dir}\\bin" }
+        }
+        "#;
+
+        let tool: Tool = serde_json::from_str(json).expect("Deberia parsear la herramienta");
+        let dir = Path::new("base").join("pwsh");
+        let dir_text = dir.to_string_lossy();
+
+        assert_eq!(
+            tool.resolved_installer_args(&dir),
+            vec![
+                "/S".to_string(),
+                format!("/DIR={}", dir_text),
+                format!("--prefix={}", dir_text),
+            ]
+        );
+        assert_eq!(
+            tool.resolved_env(&dir),
+            vec![("FOO".to_string(), format!("x;{}\\bin", dir_text))]
+        );
     }
 }
@@ -0,0 +1,119 @@
+use crate::errors::BeError;
+use serde::Serialize;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// `--output json`: el texto para personas se omite y cada comando imprime un solo JSON.
+static JSON: AtomicBool = AtomicBool::new(false);
+/// `be env`: stdout es el script que ejecuta la shell; el texto para personas va a stderr.
+static SCRIPT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json(enabled: bool) {
+    JSON.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_json() -> bool {
+    JSON.load(Ordering::Relaxed)
+}
+
+pub fn set_script(enabled: bool) {
+    SCRIPT.store(enabled, Ordering::Relaxed);
+}
+
+/// `true` si stdout esta reservado para un script y los mensajes deben ir a stderr.
+pub fn is_script() -> bool {
+    SCRIPT.load(Ordering::Relaxed)
+}
+
+/// `println!` que no escribe nada con `--output json` y usa stderr mientras `be env`
+/// genera un script.
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::output::is_script() {
+            eprintln!($($arg)*);
+        } else if !$crate::output::is_json() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Salida de los programas que lanza `be` (instaladores, PowerShell): con `--output json`
+/// o mientras se genera un script va a stderr para no mezclarse con stdout.
+pub fn child_stdout() -> Stdio {
+    if is_json() || is_script() {
+        Stdio::from(std::io::stderr())
+    } else {
+        Stdio::inherit()
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a, T: Serialize> {
+    command: &'a str,
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<&'a T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<ErrorInfo>,
+}
+
+#[derive(Serialize)]
+struct ErrorInfo {
+    code: &'static str,
+    message: String,
+}
+
+/// Resultado de un comando: `{"command", "ok": true, "result": ...}`.
+/// En modo texto no imprime nada (el comando ya lo conto).
+pub fn emit<T: Serialize>(command: &str, result: &T) -> Result<(), BeError> {
+    if !is_json() {
+        return Ok(());
+    }
+    print_envelope(&Envelope {
+        command,
+        ok: true,
+        result: Some(result),
+        error: None,
+    })
+}
+
+/// Error de un comando: `{"command", "ok": false, "error": {"code", "message"}}`.
+pub fn emit_error(command: &str, error: &BeError) -> Result<(), BeError> {
+    print_envelope(&Envelope::<()> {
+        command,
+        ok: false,
+        result: None,
+        error: Some(ErrorInfo {
+            code: error.code(),
+            message: error.to_string(),
+        }),
+    })
+}
+
+fn print_envelope<T: Serialize>(envelope: &Envelope<T>) -> Result<(), BeError> {
+    println!("{}", serde_json::to_string_pretty(envelope)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_envelope_has_code_and_message() {
+        let envelope = Envelope::<()> {
+            command: "clean",
+            ok: false,
+            result: None,
+            error: Some(ErrorInfo {
+                code: BeError::Cancelled.code(),
+                message: BeError::Cancelled.to_string(),
+            }),
+        };
+        let value = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(value["ok"], false);
+        assert_eq!(value["error"]["code"], "cancelled");
+        assert!(value.get("result").is_none());
+    }
+}
@@ -0,0 +1,268 @@
+use crate::config;
+use crate::env_store::{self, EnvStore};
+use crate::errors::BeError;
+use crate::path_list::{PathList, PathStyle};
+use crate::ui;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// Copias que se conservan; las mas viejas se borran al guardar una nueva.
+const MAX_SNAPSHOTS: usize = 50;
+
+/// Valor del PATH persistente justo antes de que `be` lo modificara.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathSnapshot {
+    pub id: String,
+    pub taken_at: String,
+    /// Comando que provoco el cambio (`setup`, `use`, `clean`, `restore`).
+    pub reason: String,
+    pub location: String,
+    /// Tipo del valor en el registro (`REG_SZ` / `REG_EXPAND_SZ`).
+    #[serde(default)]
+    pub value_type: Option<String>,
+    pub entries: Vec<String>,
+}
+
+/// Carpeta `BrisasEnv\path-history`, un JSON por copia.
+pub struct PathHistory {
+    dir: PathBuf,
+}
+
+impl PathHistory {
+    pub fn open() -> Result<Self, BeError> {
+        Ok(Self::at(dir()?))
+    }
+
+    pub fn at(dir: PathBuf) -> Self {
+        PathHistory { dir }
+    }
+
+    /// Guarda el valor actual del almacen antes de modificarlo.
+    pub fn snapshot(&self, store: &dyn EnvStore, reason: &str) -> Result<PathSnapshot, BeError> {
+        fs::create_dir_all(&self.dir)?;
+        let now = chrono::Local::now();
+        let base_id = now.format("%Y%m%d-%H%M%S").to_string();
+        let mut id = base_id.clone();
+        let mut n = 1;
+        while self.dir.join(format!("{}.json", id)).exists() {
+            n += 1;
+            id = format!("{}-{}", base_id, n);
+        }
+
+        let snapshot = PathSnapshot {
+            id,
+            taken_at: now.format("%Y-%m-%d %H:%M:%S").to_string(),
+            reason: reason.to_string(),
+            location: store.location(),
+            value_type: store.value_type()?,
+            entries: store.read_path()?,
+        };
+        fs::write(
+            self.dir.join(format!("{}.json", snapshot.id)),
+            serde_json::to_string_pretty(&snapshot)?,
+        )?;
+        self.prune()?;
+        Ok(snapshot)
+    }
+
+    /// Copias guardadas, de la mas vieja a la mas reciente.
+    pub fn list(&self) -> Result<Vec<PathSnapshot>, BeError> {
+        let mut snapshots = Vec::new();
+        if !self.dir.exists() {
+            return Ok(snapshots);
+        }
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let parsed = fs::read_to_string(&path)
+                .map_err(BeError::from)
+                .and_then(|content| Ok(serde_json::from_str::<PathSnapshot>(&content)?));
+            match parsed {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => ui::print_warning(&format!(
+                    "Se ignora la copia danada {}: {}",
+                    path.display(),
+                    e
+                )),
+            }
+        }
+        // `-10` must come after `-2`: compare the counter as a number, not as text
+        snapshots.sort_by(|a, b| {
+            (&a.taken_at, sequence(&a.id), &a.id).cmp(&(&b.taken_at, sequence(&b.id), &b.id))
+        });
+        Ok(snapshots)
+    }
+
+    /// Copia por id, o la mas reciente sin id.
+    pub fn find(&self, id: Option<&str>) -> Result<PathSnapshot, BeError> {
+        let snapshots = self.list()?;
+        let found = match id {
+            Some(id) => snapshots.into_iter().find(|s| s.id == id),
+            None => snapshots.into_iter().last(),
+        };
+        found.ok_or_else(|| match id {
+            Some(id) => BeError::Config(format!(
+                "No existe la copia '{}'. Usa 'be path history' para ver las disponibles.",
+                id
+            )),
+            None => BeError::Config("No hay copias del PATH guardadas.".into()),
+        })
+    }
+
+    fn prune(&self) -> Result<(), BeError> {
+        let snapshots = self.list()?;
+        if snapshots.len() <= MAX_SNAPSHOTS {
+            return Ok(());
+        }
+        for old in &snapshots[..snapshots.len() - MAX_SNAPSHOTS] {
+            fs::remove_file(self.dir.join(format!("{}.json", old.id)))?;
+        }
+        Ok(())
+    }
+}
+
+/// Contador de las copias tomadas en el mismo segundo (`<fecha>-<hora>-<n>`); la primera no
+/// lo lleva y cuenta como 1.
+fn sequence(id: &str) -> u32 {
+    id.splitn(3, '-')
+        .nth(2)
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1)
+}
+
+/// Carpeta del historial; `be clean` la conserva para poder deshacer la limpieza.
+pub fn dir() -> Result<PathBuf, BeError> {
+    Ok(config::state_dir()?.join("path-history"))
+}
+
+/// Escribe el PATH guardando antes una copia del valor anterior.
+pub fn write_with_backup(
+    store: &mut dyn EnvStore,
+    history: &PathHistory,
+    entries: &[String],
+    reason: &str,
+) -> Result<(), BeError> {
+    history.snapshot(store, reason)?;
+    store.write_path(entries)
+}
+
+/// `be path history`.
+pub fn print_history() -> Result<(), BeError> {
+    let snapshots = PathHistory::open()?.list()?;
+    if snapshots.is_empty() {
+        println!("No hay copias del PATH guardadas.");
+        return Ok(());
+    }
+    println!("Copias del PATH (la mas reciente al final):");
+    for s in &snapshots {
+        println!(
+            "  {:<18} {}  {:<8} {:>3} entradas  {}",
+            s.id,
+            s.taken_at,
+            s.reason,
+            s.entries.len(),
+            s.value_type.as_deref().unwrap_or("")
+        );
+    }
+    println!();
+    println!("Restaurar: be path restore <id> (sin id, la mas reciente)");
+    Ok(())
+}
+
+/// `be path restore [id]`: vuelve al valor de una copia, guardando antes el actual.
+pub fn restore(id: Option<&str>) -> Result<(), BeError> {
+    let history = PathHistory::open()?;
+    let snapshot = history.find(id)?;
+    let mut store = env_store::user_store()?;
+
+    let current = store.read_path()?;
+    let style = PathStyle::native();
+    let current_list = PathList::new(current.clone(), style);
+    let target_list = PathList::new(snapshot.entries.clone(), style);
+    for entry in snapshot
+        .entries
+        .iter()
+        .filter(|e| !current_list.contains(e))
+    {
+        ui::print_step(&format!("Se agrega: {}", entry));
+    }
+    for entry in current.iter().filter(|e| !target_list.contains(e)) {
+        ui::print_step(&format!("Se quita: {}", entry));
+    }
+
+    history.snapshot(store.as_ref(), "restore")?;
+    store.restore_path(&snapshot.entries, snapshot.value_type.as_deref())?;
+    info!("PATH restaurado desde la copia {}", snapshot.id);
+    ui::print_success(&format!(
+        "PATH restaurado a la copia {} ({}, antes de '{}').",
+        snapshot.id, snapshot.taken_at, snapshot.reason
+    ));
+    println!("Nota: Reinicia tus terminales para ver los cambios.");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env_store::MemoryStore;
+
+    #[test]
+    fn test_write_with_backup_and_restore() {
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let history = PathHistory::at(dir.path().to_path_buf());
+        let mut store = MemoryStore {
+            path: vec!["C:\\Windows".to_string()],
+            ..Default::default()
+        };
+
+        let new_path = vec!["C:\\Windows".to_string(), "C:\\be\\node".to_string()];
+        write_with_backup(&mut store, &history, &new_path, "setup").unwrap();
+        write_with_backup(&mut store, &history, &[], "clean").unwrap();
+
+        let snapshots = history.list().unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].entries, vec!["C:\\Windows"]);
+        assert_eq!(snapshots[1].reason, "clean");
+
+        let latest = history.find(None).unwrap();
+        assert_eq!(latest.entries, new_path);
+        assert!(history.find(Some("no-existe")).is_err());
+    }
+
+    #[test]
+    fn test_list_orders_same_second_numerically_and_skips_corrupt_files() {
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let history = PathHistory::at(dir.path().to_path_buf());
+        for n in 1..=11 {
+            let id = match n {
+                1 => "20250101-100000".to_string(),
+                n => format!("20250101-100000-{}", n),
+            };
+            let snapshot = PathSnapshot {
+                id: id.clone(),
+                taken_at: "2025-01-01 10:00:00".into(),
+                reason: "setup".into(),
+                location: "memoria".into(),
+                value_type: None,
+                entries: vec![format!("C:\\{}", n)],
+            };
+            fs::write(
+                dir.path().join(format!("{}.json", id)),
+                serde_json::to_string(&snapshot).unwrap(),
+            )
+            .unwrap();
+        }
+        fs::write(dir.path().join("20250101-090000.json"), "{ roto").unwrap();
+
+        let ids: Vec<String> = history.list().unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(ids.len(), 11);
+        assert_eq!(ids[0], "20250101-100000");
+        assert_eq!(ids[1], "20250101-100000-2");
+        assert_eq!(ids[10], "20250101-100000-11");
+        assert_eq!(history.find(None).unwrap().id, "20250101-100000-11");
+    }
+}
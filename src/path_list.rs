@@ -0,0 +1,371 @@
+use std::env;
+
+/// Reglas para comparar entradas de PATH.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    /// Sin distinguir mayusculas, `/` equivale a `\` y se expanden `%VAR%`.
+    Windows,
+    /// Distingue mayusculas y expande `$VAR` / `${VAR}`.
+    Posix,
+}
+
+impl PathStyle {
+    pub fn native() -> Self {
+        if cfg!(windows) {
+            PathStyle::Windows
+        } else {
+            PathStyle::Posix
+        }
+    }
+
+    /// Forma canonica de una entrada, usada solo para comparar (nunca se escribe).
+    pub fn normalize(self, entry: &str) -> String {
+        self.normalize_with(entry, &|name| env::var(name).ok())
+    }
+
+    fn normalize_with(self, entry: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+        let expanded = match self {
+            PathStyle::Windows => expand_percent_vars(entry.trim(), lookup),
+            PathStyle::Posix => expand_dollar_vars(entry.trim(), lookup),
+        };
+        match self {
+            PathStyle::Windows => {
+                let unified = expanded.replace('/', "\\").to_lowercase();
+                let trimmed = unified.trim_end_matches('\\');
+                // Keep the root of a drive ("c:\") distinct from "c:" (current dir on c:)
+                if trimmed.len() == 2 && trimmed.ends_with(':') {
+                    format!("{}\\", trimmed)
+                } else {
+                    trimmed.to_string()
+                }
+            }
+            PathStyle::Posix => {
+                let trimmed = expanded.trim_end_matches('/');
+                if trimmed.is_empty() && expanded.starts_with('/') {
+                    "/".to_string()
+                } else {
+                    trimmed.to_string()
+                }
+            }
+        }
+    }
+}
+
+/// Lo que cambio al aplicar entradas sobre un PATH.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PathDiff {
+    pub added: Vec<String>,
+    /// Entradas quitadas, incluidas las duplicadas.
+    pub removed: Vec<String>,
+}
+
+impl PathDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Entradas de un PATH en orden. Conserva el texto original de cada entrada
+/// (ej. `%LOCALAPPDATA%\node`) y compara por su forma normalizada.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathList {
+    style: PathStyle,
+    entries: Vec<String>,
+}
+
+impl PathList {
+    /// Las entradas vacias se descartan.
+    pub fn new(entries: Vec<String>, style: PathStyle) -> Self {
+        PathList {
+            style,
+            entries: entries
+                .into_iter()
+                .filter(|e| !e.trim().is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn join(&self, separator: char) -> String {
+        self.entries.join(&separator.to_string())
+    }
+
+    pub fn contains(&self, entry: &str) -> bool {
+        let key = self.style.normalize(entry);
+        self.entries.iter().any(|e| self.style.normalize(e) == key)
+    }
+
+    /// Quita las repeticiones (se conserva la primera, que es la que gana) y las devuelve.
+    pub fn dedupe(&mut self) -> Vec<String> {
+        let mut seen = Vec::new();
+        let mut removed = Vec::new();
+        let style = self.style;
+        self.entries.retain(|e| {
+            let key = style.normalize(e);
+            if seen.contains(&key) {
+                removed.push(e.clone());
+                false
+            } else {
+                seen.push(key);
+                true
+            }
+        });
+        removed
+    }
+
+    /// Quita `remove` (salvo lo que tambien esta en `add`), elimina duplicados y agrega al
+    /// final lo que falte de `add`. Solo coinciden entradas completas, nunca subcadenas.
+    pub fn apply(&mut self, add: &[String], remove: &[String]) -> PathDiff {
+        let style = self.style;
+        let keep: Vec<String> = add.iter().map(|e| style.normalize(e)).collect();
+        let drop: Vec<String> = remove
+            .iter()
+            .map(|e| style.normalize(e))
+            .filter(|key| !keep.contains(key))
+            .collect();
+
+        let mut diff = PathDiff::default();
+        self.entries.retain(|e| {
+            if drop.contains(&style.normalize(e)) {
+                diff.removed.push(e.clone());
+                false
+            } else {
+                true
+            }
+        });
+        diff.removed.extend(self.dedupe());
+
+        for entry in add {
+            if !self.contains(entry) {
+                self.entries.push(entry.clone());
+                diff.added.push(entry.clone());
+            }
+        }
+        diff
+    }
+
+    /// Reescribe cada entrada con `f` (ej. para compactarla) y dice si algo cambio.
+    pub fn map_entries(&mut self, f: impl Fn(&str) -> String) -> bool {
+        let mut changed = false;
+        for entry in &mut self.entries {
+            let mapped = f(entry);
+            if mapped != *entry {
+                *entry = mapped;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// Pone `entries` al principio (en ese orden) y quita sus otras apariciones.
+    pub fn prepend(&mut self, entries: &[String]) {
+        let mut result = entries.to_vec();
+        result.append(&mut self.entries);
+        self.entries = result;
+        self.dedupe();
+    }
+}
+
+/// Reescribe `entry` como `%VAR%\resto` si empieza con el valor de alguna variable
+/// (ej. `C:\Users\Ana\AppData\Local\node` -> `%LOCALAPPDATA%\node`). Gana el valor mas largo.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub fn contract_vars(entry: &str, vars: &[(String, String)]) -> String {
+    let mut vars: Vec<&(String, String)> = vars.iter().filter(|(_, v)| !v.is_empty()).collect();
+    vars.sort_by_key(|(_, value)| std::cmp::Reverse(value.len()));
+    for (name, value) in vars {
+        let value = value.trim_end_matches('\\');
+        let Some(prefix) = entry.get(..value.len()) else {
+            continue;
+        };
+        let rest = &entry[value.len()..];
+        if prefix.eq_ignore_ascii_case(value)
+            && (rest.is_empty() || rest.starts_with('\\') || rest.starts_with('/'))
+        {
+            return format!("%{}%{}", name, rest);
+        }
+    }
+    entry.to_string()
+}
+
+fn expand_percent_vars(value: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => match lookup(&after[..end]) {
+                Some(val) if end > 0 => {
+                    out.push_str(&val);
+                    rest = &after[end + 1..];
+                }
+                // Unknown variables are left as written, like cmd.exe does
+                _ => {
+                    out.push('%');
+                    rest = after;
+                }
+            },
+            None => {
+                out.push('%');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn expand_dollar_vars(value: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut out = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        let braced = chars.peek() == Some(&'{');
+        if braced {
+            chars.next();
+        }
+        let mut name = String::new();
+        while let Some(&n) = chars.peek() {
+            if n.is_ascii_alphanumeric() || n == '_' {
+                name.push(n);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if braced && chars.peek() == Some(&'}') {
+            chars.next();
+        }
+        match lookup(&name) {
+            Some(val) if !name.is_empty() => out.push_str(&val),
+            _ if braced => out.push_str(&format!("${{{}}}", name)),
+            _ => {
+                out.push('$');
+                out.push_str(&name);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(entries: &[&str], style: PathStyle) -> PathList {
+        PathList::new(entries.iter().map(|e| e.to_string()).collect(), style)
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_windows_normalize_case_slashes_and_vars() {
+        let lookup = |name: &str| {
+            (name.eq_ignore_ascii_case("LOCALAPPDATA"))
+                .then(|| "C:\\Users\\Ana\\AppData\\Local".to_string())
+        };
+        let style = PathStyle::Windows;
+        let expected = "c:\\users\\ana\\appdata\\local\\node";
+        assert_eq!(
+            style.normalize_with("C:\\Users\\Ana\\AppData\\Local\\node\\", &lookup),
+            expected
+        );
+        assert_eq!(
+            style.normalize_with("%LOCALAPPDATA%/Node", &lookup),
+            expected
+        );
+        assert_eq!(style.normalize_with("%NOPE%\\bin", &lookup), "%nope%\\bin");
+        assert_eq!(style.normalize_with("C:\\", &lookup), "c:\\");
+    }
+
+    #[test]
+    fn test_posix_normalize_keeps_case() {
+        let lookup = |name: &str| (name == "HOME").then(|| "/home/ana".to_string());
+        let style = PathStyle::Posix;
+        assert_eq!(
+            style.normalize_with("${HOME}/bin/", &lookup),
+            "/home/ana/bin"
+        );
+        assert_eq!(style.normalize_with("$HOME/Bin", &lookup), "/home/ana/Bin");
+        assert_eq!(style.normalize_with("/", &lookup), "/");
+    }
+
+    #[test]
+    fn test_apply_removes_whole_entries_only() {
+        let mut path = list(
+            &[
+                "C:\\Windows",
+                "C:\\Local\\node",
+                "C:\\Local\\node\\something-else",
+            ],
+            PathStyle::Windows,
+        );
+        let diff = path.apply(&[], &strings(&["c:\\local\\NODE\\"]));
+        assert_eq!(diff.removed, strings(&["C:\\Local\\node"]));
+        assert_eq!(
+            path.entries(),
+            strings(&["C:\\Windows", "C:\\Local\\node\\something-else"])
+        );
+    }
+
+    #[test]
+    fn test_apply_does_not_add_case_variants_and_dedupes() {
+        let mut path = list(
+            &["C:\\Local\\node\\", "C:\\Windows", "c:\\windows"],
+            PathStyle::Windows,
+        );
+        let diff = path.apply(&strings(&["C:\\LOCAL\\node", "C:\\Local\\git\\cmd"]), &[]);
+        assert_eq!(diff.added, strings(&["C:\\Local\\git\\cmd"]));
+        assert_eq!(diff.removed, strings(&["c:\\windows"]));
+        assert_eq!(path.entries().len(), 3);
+    }
+
+    #[test]
+    fn test_apply_keeps_entries_both_added_and_removed() {
+        let mut path = list(&["/opt/node/22/bin"], PathStyle::Posix);
+        let diff = path.apply(
+            &strings(&["/opt/node/22/bin/"]),
+            &strings(&["/opt/node/22/bin", "/opt/node/18/bin"]),
+        );
+        assert!(diff.is_empty());
+    }
+
+    #[test]
+    fn test_prepend_moves_entries_to_front() {
+        let mut path = list(&["/usr/bin", "/opt/node/bin", "", "/bin"], PathStyle::Posix);
+        path.prepend(&strings(&["/opt/node/bin", "/opt/git/bin"]));
+        assert_eq!(path.join(';'), "/opt/node/bin;/opt/git/bin;/usr/bin;/bin");
+    }
+
+    #[test]
+    fn test_contract_vars_prefers_longest_prefix() {
+        let vars = vec![
+            ("USERPROFILE".to_string(), "C:\\Users\\Ana".to_string()),
+            (
+                "LOCALAPPDATA".to_string(),
+                "C:\\Users\\Ana\\AppData\\Local".to_string(),
+            ),
+        ];
+        assert_eq!(
+            contract_vars("c:\\users\\ana\\AppData\\Local\\node\\22.12.0", &vars),
+            "%LOCALAPPDATA%\\node\\22.12.0"
+        );
+        assert_eq!(
+            contract_vars("C:\\Users\\Ana\\.cargo\\bin", &vars),
+            "%USERPROFILE%\\.cargo\\bin"
+        );
+        // Only whole path components match
+        assert_eq!(
+            contract_vars("C:\\Users\\Anabel\\bin", &vars),
+            "C:\\Users\\Anabel\\bin"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::cache::DownloadCache;
+use crate::download;
+use crate::manifest::Tool;
+use crate::say;
+use indicatif::HumanBytes;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Una descarga prevista por `--dry-run`.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedDownload {
+    pub tool: String,
+    pub url: String,
+    /// Tamano informado por el servidor (HEAD). `None` si no respondio o ya esta en cache.
+    pub size: Option<u64>,
+    pub cached: bool,
+}
+
+/// Lo que `be setup` o `be clean` harian, calculado sin tocar disco ni registro.
+#[derive(Debug, Default, Serialize)]
+pub struct Plan {
+    pub downloads: Vec<PlannedDownload>,
+    pub create_dirs: Vec<PathBuf>,
+    pub remove_dirs: Vec<PathBuf>,
+    pub path_add: Vec<String>,
+    pub path_remove: Vec<String>,
+    pub shortcuts: Vec<PathBuf>,
+    pub remove_files: Vec<PathBuf>,
+}
+
+impl Plan {
+    /// Registra una descarga; solo consulta el tamano remoto si no esta en cache.
+    pub fn add_download(&mut self, tool: &Tool) {
+        let cached = matches!(
+            DownloadCache::key(&tool.url, tool.sha256.as_deref())
+                .and_then(|key| DownloadCache::open()?.find(&key)),
+            Ok(Some(_))
+        );
+        let size = if cached {
+            None
+        } else {
+            download::remote_size(&tool.url)
+        };
+        self.downloads.push(PlannedDownload {
+            tool: tool.name.clone(),
+            url: tool.url.clone(),
+            size,
+            cached,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.downloads.is_empty()
+            && self.create_dirs.is_empty()
+            && self.remove_dirs.is_empty()
+            && self.path_add.is_empty()
+            && self.path_remove.is_empty()
+            && self.shortcuts.is_empty()
+            && self.remove_files.is_empty()
+    }
+
+    pub fn print(&self, title: &str) {
+        say!();
+        say!("PLAN: {} (--dry-run)", title);
+        say!("--------------------------------------");
+        if self.is_empty() {
+            say!("  Nada que hacer.");
+        }
+
+        if !self.downloads.is_empty() {
+            say!("Descargas:");
+            let mut total = 0;
+            for d in &self.downloads {
+                let detail = if d.cached {
+                    "en cache".to_string()
+                } else if let Some(size) = d.size {
+                    total += size;
+                    HumanBytes(size).to_string()
+                } else {
+                    "tamano desconocido".to_string()
+                };
+                say!("  {:<10} {} ({})", d.tool, d.url, detail);
+            }
+            if total > 0 {
+                say!("  Total a descargar: {}", HumanBytes(total));
+            }
+        }
+        print_list("Carpetas a crear:", &self.create_dirs);
+        print_list("Carpetas a eliminar:", &self.remove_dirs);
+        print_list("Archivos a eliminar:", &self.remove_files);
+        print_entries("PATH (agregar):", &self.path_add);
+        print_entries("PATH (quitar):", &self.path_remove);
+        print_list("Accesos directos:", &self.shortcuts);
+
+        say!();
+        say!("Nada se modifico (--dry-run).");
+    }
+}
+
+fn print_list(label: &str, items: &[PathBuf]) {
+    let items: Vec<String> = items.iter().map(|p| p.display().to_string()).collect();
+    print_entries(label, &items);
+}
+
+fn print_entries(label: &str, items: &[String]) {
+    if items.is_empty() {
+        return;
+    }
+    say!("{}", label);
+    for item in items {
+        say!("  {}", item);
+    }
+}
@@ -0,0 +1,506 @@
+use crate::config;
+use crate::errors::BeError;
+use crate::installer::{self, InstallAction};
+use crate::manifest::{Manifest, Tool};
+use crate::receipt::{ActiveVersions, Receipt};
+use crate::ui;
+use crate::version;
+use log::warn;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_FILE: &str = ".brisas.toml";
+
+/// Archivos de otros gestores (asdf, nvm, rustup) que tambien se respetan.
+/// Dentro de una misma carpeta gana el primero de la lista.
+const FOREIGN_FILES: [&str; 5] = [
+    ".tool-versions",
+    ".nvmrc",
+    ".node-version",
+    "rust-toolchain.toml",
+    "rust-toolchain",
+];
+
+/// Contenido de `.brisas.toml`:
+///
+/// ```toml
+/// [tools]
+/// node = "18.20.4"
+/// mingw64 = "14"
+/// ```
+#[derive(Debug, Deserialize, Default)]
+pub struct ProjectFile {
+    /// Herramienta -> version exacta o prefijo (`18` acepta `18.20.4`).
+    #[serde(default)]
+    pub tools: BTreeMap<String, String>,
+}
+
+/// Una version pedida por algun archivo del proyecto.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Requirement {
+    pub tool: String,
+    /// Version exacta o prefijo. `None` acepta la version activa o la mas nueva instalada.
+    pub version: Option<String>,
+    /// Variables extra para el proceso hijo (ej. `RUSTUP_TOOLCHAIN`).
+    pub env: Vec<(String, String)>,
+    /// Lo que pidio el archivo, para `be which-version` (ej. `toolchain 1.80.0`).
+    pub detail: String,
+    pub source: PathBuf,
+}
+
+/// Que se encontro para un requisito, sin instalar nada.
+#[derive(Debug)]
+pub enum Selection {
+    Installed(Receipt),
+    /// No esta instalado pero el manifiesto tiene una version que coincide.
+    Installable(Tool),
+    Missing,
+}
+
+#[derive(Debug)]
+pub struct Resolved {
+    pub requirement: Requirement,
+    pub selection: Selection,
+}
+
+/// Herramienta elegida para el entorno de `be run`, con las variables extra del requisito.
+#[derive(Debug)]
+pub struct Selected {
+    pub receipt: Receipt,
+    pub extra_env: Vec<(String, String)>,
+}
+
+/// Requisitos encontrados desde un directorio hacia arriba.
+#[derive(Debug, Default)]
+pub struct ProjectRequirements {
+    /// Un requisito por herramienta: gana la carpeta mas cercana.
+    pub requirements: Vec<Requirement>,
+    /// Si hay un `.brisas.toml`, el entorno se limita a lo declarado en los archivos.
+    pub project_file: Option<PathBuf>,
+}
+
+impl ProjectRequirements {
+    /// Sube desde `start` hasta la primera carpeta con `.brisas.toml` (incluida) o hasta la
+    /// raiz. Lo que haya por encima del proyecto (ej. un `~/.tool-versions` global) no entra.
+    pub fn discover(start: &Path) -> Result<Self, BeError> {
+        let mut found = ProjectRequirements::default();
+
+        for dir in start.ancestors() {
+            let brisas = dir.join(PROJECT_FILE);
+            if brisas.is_file() {
+                found.project_file = Some(brisas.clone());
+                let content = fs::read_to_string(&brisas)?;
+                let file: ProjectFile = toml::from_str(&content).map_err(|e| {
+                    BeError::Config(format!("{} invalido: {}", brisas.display(), e))
+                })?;
+                for (tool, version) in file.tools {
+                    found.add(Requirement {
+                        detail: version.clone(),
+                        tool,
+                        version: Some(version),
+                        env: Vec::new(),
+                        source: brisas.clone(),
+                    });
+                }
+            }
+
+            for name in FOREIGN_FILES {
+                let path = dir.join(name);
+                if !path.is_file() {
+                    continue;
+                }
+                let content = fs::read_to_string(&path)?;
+                for requirement in parse_foreign(name, &content, &path) {
+                    found.add(requirement);
+                }
+            }
+
+            if found.project_file.is_some() {
+                break;
+            }
+        }
+        Ok(found)
+    }
+
+    fn add(&mut self, requirement: Requirement) {
+        if !self.requirements.iter().any(|r| r.tool == requirement.tool) {
+            self.requirements.push(requirement);
+        }
+    }
+
+    /// Busca cada requisito entre los recibos instalados y luego en `manifest` (el mismo
+    /// que ya cargo quien llama, para no leerlo dos veces).
+    pub fn select(&self, manifest: &Manifest) -> Result<Vec<Resolved>, BeError> {
+        let active = ActiveVersions::load()?;
+        let mut resolved = Vec::new();
+
+        for requirement in &self.requirements {
+            let selection = if let Some(receipt) = find_installed(requirement, &active)? {
+                Selection::Installed(receipt)
+            } else {
+                match manifest.tools.iter().find(|t| {
+                    t.name == requirement.tool
+                        && requirement
+                            .version
+                            .as_deref()
+                            .is_none_or(|v| version::matches(v, &t.version))
+                }) {
+                    Some(tool) => Selection::Installable(tool.clone()),
+                    None => Selection::Missing,
+                }
+            };
+            resolved.push(Resolved {
+                requirement: requirement.clone(),
+                selection,
+            });
+        }
+        Ok(resolved)
+    }
+
+    /// Recibos y variables extra para el proceso hijo, instalando lo que falte.
+    /// Sin `.brisas.toml`, las herramientas no mencionadas usan su version activa.
+    pub fn resolve_for_run(&self, manifest: &Manifest) -> Result<Vec<Selected>, BeError> {
+        let mut selected = Vec::new();
+
+        for resolved in self.select(manifest)? {
+            let requirement = resolved.requirement;
+            let receipt = match resolved.selection {
+                Selection::Installed(receipt) => receipt,
+                Selection::Installable(tool) => {
+                    ui::print_step(&format!(
+                        "{} pide {} {}: instalando...",
+                        file_name(&requirement.source),
+                        tool.name,
+                        tool.version
+                    ));
+                    installer::install_tool(
+                        &tool,
+                        &config::tools_root()?,
+                        &InstallAction::Install,
+                    )?;
+                    Receipt::load(&tool.name, &tool.version)?.ok_or_else(|| {
+                        BeError::Setup(format!("No se encontro el recibo de {}.", tool.name))
+                    })?
+                }
+                Selection::Missing if file_name(&requirement.source) == PROJECT_FILE => {
+                    return Err(BeError::Config(format!(
+                        "{} {} (pedido por {}) no esta instalado ni disponible en el manifiesto.",
+                        requirement.tool,
+                        requirement.detail,
+                        requirement.source.display()
+                    )));
+                }
+                Selection::Missing => {
+                    // Foreign files may mention tools Brisas does not manage
+                    warn!(
+                        "Ignorando {} {} de {}: no disponible.",
+                        requirement.tool,
+                        requirement.detail,
+                        requirement.source.display()
+                    );
+                    ui::print_warning(&format!(
+                        "{} pide {} {}, que no esta instalado ni en el manifiesto: se ignora.",
+                        file_name(&requirement.source),
+                        requirement.tool,
+                        requirement.detail
+                    ));
+                    continue;
+                }
+            };
+            selected.push(Selected {
+                receipt,
+                extra_env: requirement.env,
+            });
+        }
+
+        if self.project_file.is_none() {
+            let active = ActiveVersions::load()?;
+            for (tool_name, version) in &active.tools {
+                if selected.iter().any(|s| s.receipt.tool == *tool_name) {
+                    continue;
+                }
+                if let Some(receipt) = Receipt::load(tool_name, version)? {
+                    selected.push(Selected {
+                        receipt,
+                        extra_env: Vec::new(),
+                    });
+                }
+            }
+        }
+        Ok(selected)
+    }
+}
+
+/// `be which-version [tool]`: explica que version se usara y que archivo la pidio.
+pub fn explain(tool_filter: Option<&str>) -> Result<(), BeError> {
+    let cwd = std::env::current_dir()?;
+    let found = ProjectRequirements::discover(&cwd)?;
+    let wanted = |name: &str| tool_filter.is_none_or(|t| t == name);
+    let manifest = Manifest::load_local_or_default()?;
+
+    println!("Versiones para {}:", cwd.display());
+    let mut mentioned = Vec::new();
+    for resolved in found.select(&manifest)? {
+        let req = &resolved.requirement;
+        mentioned.push(req.tool.clone());
+        if !wanted(&req.tool) {
+            continue;
+        }
+        let version = match &resolved.selection {
+            Selection::Installed(receipt) => receipt.version.clone(),
+            Selection::Installable(tool) => format!("{} (se instalara)", tool.version),
+            Selection::Missing => "no disponible".to_string(),
+        };
+        println!(
+            "  {:<10} {:<22} <- {}: {} ({})",
+            req.tool,
+            version,
+            file_name(&req.source),
+            req.detail,
+            req.source.display()
+        );
+    }
+
+    if let Some(project_file) = &found.project_file {
+        println!(
+            "\nNota: {} limita el entorno a las herramientas declaradas.",
+            project_file.display()
+        );
+    } else {
+        for (tool_name, version) in &ActiveVersions::load()?.tools {
+            if !mentioned.contains(tool_name) && wanted(tool_name) {
+                println!(
+                    "  {:<10} {:<22} <- version activa (be use)",
+                    tool_name, version
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// La version instalada que cumple el requisito: exacta, o la mas nueva con ese prefijo.
+/// Sin version pedida se prefiere la activa.
+fn find_installed(
+    requirement: &Requirement,
+    active: &ActiveVersions,
+) -> Result<Option<Receipt>, BeError> {
+    let installed = Receipt::list_versions(&requirement.tool)?;
+    let Some(requested) = requirement.version.as_deref() else {
+        let active_version = active.get(&requirement.tool);
+        return Ok(installed
+            .iter()
+            .find(|r| Some(r.version.as_str()) == active_version)
+            .or(installed.last())
+            .cloned());
+    };
+    if let Some(exact) = installed.iter().find(|r| r.version == requested) {
+        return Ok(Some(exact.clone()));
+    }
+    Ok(installed
+        .into_iter()
+        .rev()
+        .find(|r| version::matches(requested, &r.version)))
+}
+
+fn parse_foreign(name: &str, content: &str, source: &Path) -> Vec<Requirement> {
+    let parsed = match name {
+        ".tool-versions" => parse_tool_versions(content),
+        ".nvmrc" | ".node-version" => parse_nvmrc(content).into_iter().collect(),
+        _ => parse_rust_toolchain(content).into_iter().collect(),
+    };
+    parsed
+        .into_iter()
+        .map(|mut r| {
+            r.source = source.to_path_buf();
+            r
+        })
+        .collect()
+}
+
+fn requirement(tool: &str, version: Option<&str>, detail: &str) -> Requirement {
+    Requirement {
+        tool: tool.to_string(),
+        version: version.map(str::to_string),
+        env: Vec::new(),
+        detail: detail.to_string(),
+        source: PathBuf::new(),
+    }
+}
+
+/// `.nvmrc` / `.node-version`: una version como `18`, `v18.20.4`. Los alias de nvm
+/// (`lts/*`, `node`, `stable`) no corresponden a una version concreta y se ignoran.
+fn parse_nvmrc(content: &str) -> Option<Requirement> {
+    let line = content.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let version = line.strip_prefix('v').unwrap_or(line);
+    if !version.starts_with(|c: char| c.is_ascii_digit()) {
+        warn!("Alias de nvm no soportado: {}", line);
+        return None;
+    }
+    Some(requirement("node", Some(version), version))
+}
+
+/// `.tool-versions` de asdf: `nodejs 18.20.4`, `rust 1.80.0`. Si una linea trae varias
+/// versiones se usa la primera, igual que asdf.
+fn parse_tool_versions(content: &str) -> Vec<Requirement> {
+    let mut found = Vec::new();
+    for line in content.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut parts = line.split_whitespace();
+        let (Some(name), Some(version)) = (parts.next(), parts.next()) else {
+            continue;
+        };
+        match name {
+            "nodejs" | "node" => found.push(requirement("node", Some(version), version)),
+            // Rust versions are toolchains managed by rustup, not rustup releases
+            "rust" => {
+                let mut req = requirement("rustup", None, &format!("toolchain {}", version));
+                req.env
+                    .push(("RUSTUP_TOOLCHAIN".to_string(), version.to_string()));
+                found.push(req);
+            }
+            other => found.push(requirement(other, Some(version), version)),
+        }
+    }
+    found
+}
+
+/// `rust-toolchain.toml` (`[toolchain] channel = "..."`) o el formato antiguo de una linea.
+/// rustup aplica el archivo por su cuenta; aqui solo se asegura que rustup este en el entorno.
+fn parse_rust_toolchain(content: &str) -> Option<Requirement> {
+    #[derive(Deserialize)]
+    struct ToolchainFile {
+        toolchain: ToolchainSection,
+    }
+    #[derive(Deserialize)]
+    struct ToolchainSection {
+        channel: Option<String>,
+    }
+
+    let channel = match toml::from_str::<ToolchainFile>(content) {
+        Ok(file) => file.toolchain.channel.unwrap_or_else(|| "default".into()),
+        Err(_) => content
+            .lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())?
+            .to_string(),
+    };
+    Some(requirement(
+        "rustup",
+        None,
+        &format!("toolchain {}", channel),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_project_file() {
+        let file: ProjectFile = toml::from_str(
+            r#"
+            [tools]
+            node = "18.20.4"
+            mingw64 = "14"
+            "#,
+        )
+        .expect("Deberia parsear .brisas.toml");
+
+        assert_eq!(file.tools.get("node").map(String::as_str), Some("18.20.4"));
+        assert_eq!(file.tools.get("mingw64").map(String::as_str), Some("14"));
+    }
+
+    #[test]
+    fn test_discover_walks_up_and_nearest_wins() {
+        let root = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let nested = root.path().join("src").join("app");
+        fs::create_dir_all(&nested).expect("Fallo al crear subcarpetas");
+        fs::write(
+            root.path().join(PROJECT_FILE),
+            "[tools]\nnode = \"22\"\nmingw64 = \"14\"\n",
+        )
+        .expect("Fallo al escribir .brisas.toml");
+        fs::write(nested.join(".nvmrc"), "v18.20.4\n").expect("Fallo al escribir .nvmrc");
+
+        let found = ProjectRequirements::discover(&nested).expect("Deberia leer los archivos");
+        assert_eq!(found.project_file, Some(root.path().join(PROJECT_FILE)));
+
+        let node = &found.requirements[0];
+        assert_eq!(node.tool, "node");
+        assert_eq!(node.version.as_deref(), Some("18.20.4"));
+        assert_eq!(node.source, nested.join(".nvmrc"));
+        assert!(found
+            .requirements
+            .iter()
+            .any(|r| r.tool == "mingw64" && r.version.as_deref() == Some("14")));
+    }
+
+    #[test]
+    fn test_discover_stops_at_project_file() {
+        let home = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let project = home.path().join("proyecto");
+        fs::create_dir_all(&project).expect("Fallo al crear subcarpetas");
+        fs::write(
+            home.path().join(".tool-versions"),
+            "python 3.12.0\nnode 20.0.0\n",
+        )
+        .expect("Fallo al escribir .tool-versions");
+        fs::write(
+            home.path().join(PROJECT_FILE),
+            "[tools]\nmingw64 = \"14\"\n",
+        )
+        .expect("Fallo al escribir .brisas.toml");
+        fs::write(project.join(PROJECT_FILE), "[tools]\nnode = \"22\"\n")
+            .expect("Fallo al escribir .brisas.toml");
+
+        let found = ProjectRequirements::discover(&project).expect("Deberia leer los archivos");
+        assert_eq!(found.project_file, Some(project.join(PROJECT_FILE)));
+        assert_eq!(found.requirements.len(), 1);
+        assert_eq!(found.requirements[0].tool, "node");
+        assert_eq!(found.requirements[0].version.as_deref(), Some("22"));
+    }
+
+    #[test]
+    fn test_parse_nvmrc_ignores_aliases() {
+        assert_eq!(
+            parse_nvmrc("v18.20.4\n").and_then(|r| r.version),
+            Some("18.20.4".to_string())
+        );
+        assert!(parse_nvmrc("lts/*\n").is_none());
+        assert!(parse_nvmrc("\n").is_none());
+    }
+
+    #[test]
+    fn test_parse_tool_versions() {
+        let found = parse_tool_versions("# comentario\nnodejs 20.11.1 18.0.0\nrust 1.80.0\n");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].tool, "node");
+        assert_eq!(found[0].version.as_deref(), Some("20.11.1"));
+        assert_eq!(found[1].tool, "rustup");
+        assert_eq!(found[1].version, None);
+        assert_eq!(
+            found[1].env,
+            vec![("RUSTUP_TOOLCHAIN".to_string(), "1.80.0".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_parse_rust_toolchain_formats() {
+        let toml_file = parse_rust_toolchain("[toolchain]\nchannel = \"1.80.0\"\n")
+            .expect("Deberia leer el canal");
+        assert_eq!(toml_file.detail, "toolchain 1.80.0");
+
+        let legacy = parse_rust_toolchain("nightly-2024-05-01\n").expect("Formato antiguo");
+        assert_eq!(legacy.detail, "toolchain nightly-2024-05-01");
+    }
+}
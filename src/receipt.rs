@@ -0,0 +1,181 @@
+use crate::config;
+use crate::errors::BeError;
+use crate::manifest::Tool;
+use crate::version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Registro de lo que el instalador dejo en disco para una version de una herramienta.
+/// Se guarda en `BrisasEnv\receipts\<tool>\<version>.json`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Receipt {
+    pub tool: String,
+    pub version: String,
+    pub url: String,
+    pub sha256: Option<String>,
+    pub installed_at: String,
+    pub install_dir: PathBuf,
+    /// Entradas de PATH (ya resueltas) que pertenecen a esta herramienta.
+    #[serde(default)]
+    pub path_entries: Vec<PathBuf>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Accesos directos creados por `be setup`.
+    #[serde(default)]
+    pub shortcuts: Vec<PathBuf>,
+    /// Carpetas fuera de `install_dir` que no existian antes de instalar.
+    #[serde(default)]
+    pub created_dirs: Vec<PathBuf>,
+}
+
+impl Receipt {
+    pub fn new(tool: &Tool, install_dir: &Path, created_dirs: Vec<PathBuf>) -> Self {
+        Receipt {
+            tool: tool.name.clone(),
+            version: tool.version.clone(),
+            url: tool.url.clone(),
+            sha256: tool.sha256.clone(),
+            installed_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            install_dir: install_dir.to_path_buf(),
+            path_entries: tool.resolved_path_entries(install_dir),
+            env: tool.resolved_env(install_dir).into_iter().collect(),
+            shortcuts: Vec::new(),
+            created_dirs,
+        }
+    }
+
+    pub fn load(tool_name: &str, version: &str) -> Result<Option<Self>, BeError> {
+        let path = receipt_path(tool_name, version)?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    /// Recibo de la version activa de la herramienta, si hay alguna.
+    pub fn load_active(tool_name: &str) -> Result<Option<Self>, BeError> {
+        match ActiveVersions::load()?.get(tool_name) {
+            Some(version) => Self::load(tool_name, version),
+            None => Ok(None),
+        }
+    }
+
+    /// Todas las versiones instaladas de una herramienta, de menor a mayor.
+    pub fn list_versions(tool_name: &str) -> Result<Vec<Self>, BeError> {
+        let dir = receipts_dir()?.join(tool_name);
+        let mut receipts = Vec::new();
+        if !dir.exists() {
+            return Ok(receipts);
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let content = fs::read_to_string(&path)?;
+                receipts.push(serde_json::from_str::<Receipt>(&content)?);
+            }
+        }
+        receipts.sort_by(|a, b| version::compare(&a.version, &b.version));
+        Ok(receipts)
+    }
+
+    pub fn save(&self) -> Result<(), BeError> {
+        let path = receipt_path(&self.tool, &self.version)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Borra los recibos de todas las versiones de una herramienta.
+    pub fn delete_all(tool_name: &str) -> Result<(), BeError> {
+        let dir = receipts_dir()?.join(tool_name);
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(())
+    }
+
+    pub fn delete(tool_name: &str, version: &str) -> Result<(), BeError> {
+        let path = receipt_path(tool_name, version)?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Herramientas con al menos un recibo, en orden alfabetico.
+pub fn installed_tools() -> Result<Vec<String>, BeError> {
+    let dir = receipts_dir()?;
+    let mut names = Vec::new();
+    if !dir.exists() {
+        return Ok(names);
+    }
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.path().is_dir() {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+fn receipts_dir() -> Result<PathBuf, BeError> {
+    Ok(config::state_dir()?.join("receipts"))
+}
+
+fn receipt_path(tool_name: &str, version: &str) -> Result<PathBuf, BeError> {
+    Ok(receipts_dir()?
+        .join(tool_name)
+        .join(format!("{}.json", version)))
+}
+
+/// Version activa de cada herramienta (`BrisasEnv\active.json`).
+/// Es la que usan el PATH del registro, `be run` y `be shell`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ActiveVersions {
+    #[serde(flatten)]
+    pub tools: BTreeMap<String, String>,
+}
+
+impl ActiveVersions {
+    pub fn load() -> Result<Self, BeError> {
+        let path = active_path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self) -> Result<(), BeError> {
+        let path = active_path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, tool_name: &str) -> Option<&str> {
+        self.tools.get(tool_name).map(String::as_str)
+    }
+
+    pub fn set(&mut self, tool_name: &str, version: &str) {
+        self.tools
+            .insert(tool_name.to_string(), version.to_string());
+    }
+
+    pub fn remove(&mut self, tool_name: &str) {
+        self.tools.remove(tool_name);
+    }
+}
+
+fn active_path() -> Result<PathBuf, BeError> {
+    Ok(config::state_dir()?.join("active.json"))
+}
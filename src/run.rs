@@ -1,37 +1,109 @@
-use crate::config::EnvConfig;
+use crate::environment::Environment;
+use crate::errors::BeError;
+use crate::path_list::{PathList, PathStyle};
+use log::info;
 use std::env;
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::io;
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
 
-pub fn run_command(config: &EnvConfig, cmd: &str, args: &[String]) {
+/// Ejecuta `cmd` con el entorno portable y devuelve su codigo de salida.
+pub fn run_command(environment: &Environment, cmd: &str, args: &[String]) -> Result<i32, BeError> {
     let mut command = Command::new(cmd);
     command.args(args);
 
-    if let Some(ref node_path) = config.node_path {
-        inject_path(&mut command, node_path);
-        command.env("NODE_PATH", Path::new(node_path).join("node_modules"));
-    }
-
-    if let Some(ref mingw_path) = config.mingw_path {
-        let bin = Path::new(mingw_path).join("bin");
-        inject_path(&mut command, &bin.to_string_lossy());
-        command.env("CC", bin.join("gcc.exe"));
-        command.env("CXX", bin.join("g++.exe"));
-    }
+    command.envs(child_env(environment)?);
 
     command
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .stdin(Stdio::inherit());
 
-    let _ = command.status();
+    let status = command.status().map_err(|e| {
+        if e.kind() == io::ErrorKind::NotFound {
+            BeError::Config(format!(
+                "No se encontro el comando '{}' ni en el entorno portable ni en el PATH.",
+                cmd
+            ))
+        } else {
+            BeError::Io(e)
+        }
+    })?;
+    info!("{} termino con {}", cmd, status);
+    Ok(exit_code(status))
 }
 
-fn inject_path(cmd: &mut Command, new_path: &str) {
-    if let Ok(current_path) = env::var("PATH") {
-        let new = format!("{};{}", new_path, current_path);
-        cmd.env("PATH", new);
-    } else {
-        cmd.env("PATH", new_path);
+/// Codigo con el que debe salir `be`. En Unix un proceso terminado por una senal
+/// sale como hace la shell: 128 + numero de senal.
+fn exit_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
+
+/// Variables que cambian en el proceso hijo: el PATH con las carpetas portables delante y
+/// las variables de las herramientas. `be env` escribe las mismas en el script de activacion.
+pub fn child_env(environment: &Environment) -> Result<Vec<(String, String)>, BeError> {
+    let mut vars = Vec::new();
+    if let Some(path) = inject_path(&environment.path_entries)? {
+        vars.push(("PATH".to_string(), path));
+    }
+    vars.extend(environment.vars.iter().cloned());
+    Ok(vars)
+}
+
+/// Antepone las carpetas portables al PATH heredado, sin repetir entradas que ya tenia.
+/// Usa el separador del sistema (`;` en Windows, `:` en Linux/macOS).
+fn inject_path(new_paths: &[PathBuf]) -> Result<Option<String>, BeError> {
+    if new_paths.is_empty() {
+        return Ok(None);
+    }
+    let new_entries: Vec<String> = new_paths
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+
+    let current_path = env::var_os("PATH").unwrap_or_default();
+    let current: Vec<String> = env::split_paths(&current_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let mut path = PathList::new(current, PathStyle::native());
+    path.prepend(&new_entries);
+    let joined = env::join_paths(path.entries()).map_err(|e| {
+        BeError::Config(format!(
+            "Una carpeta del entorno no se puede agregar al PATH: {}",
+            e
+        ))
+    })?;
+    Ok(Some(joined.to_string_lossy().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inject_path_uses_system_separator() {
+        let portable = env::temp_dir().join("be-node").join("bin");
+        let path = inject_path(std::slice::from_ref(&portable))
+            .unwrap()
+            .unwrap();
+        let entries: Vec<PathBuf> = env::split_paths(&path).collect();
+        assert_eq!(entries[0], portable);
+        assert_eq!(entries.iter().filter(|e| **e == portable).count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_inject_path_rejects_entries_with_separator() {
+        assert!(inject_path(&[PathBuf::from("/opt/a:b")]).is_err());
     }
 }
@@ -1,41 +1,186 @@
+use crate::admin;
+use crate::cache::{self, DownloadCache};
+use crate::config;
+use crate::env_store::{self, EnvStore};
 use crate::errors::BeError;
 use crate::installer;
-use crate::manifest::Manifest;
+use crate::manifest::{InstallKind, Manifest, Tool};
+use crate::output;
+use crate::path_history::{self, PathHistory};
+use crate::path_list::{PathDiff, PathList, PathStyle};
+use crate::plan::Plan;
+use crate::receipt::{self, ActiveVersions, Receipt};
+use crate::say;
 use crate::ui;
-use inquire::MultiSelect;
+use crate::version;
+use inquire::{Confirm, MultiSelect};
 use log::{error, info};
-use std::env;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
-use winreg::enums::*;
-use winreg::RegKey;
 
-pub fn setup_system() -> Result<(), BeError> {
+/// Opciones de `be setup`. Sin seleccion explicita se pregunta con un menu,
+/// salvo que no haya terminal interactiva.
+#[derive(Debug, Default)]
+pub struct SetupOptions {
+    /// Herramientas pedidas con `--tools node,git`.
+    pub tools: Vec<String>,
+    /// `--all`: todo el manifiesto.
+    pub all: bool,
+    /// `--profile web`: las herramientas de un perfil del manifiesto.
+    pub profile: Option<String>,
+    /// `--yes`: usar la seleccion por defecto y aceptar las confirmaciones.
+    pub yes: bool,
+    /// `--dry-run`: mostrar el plan sin descargar ni escribir nada.
+    pub dry_run: bool,
+}
+
+/// Resultado de `be setup`; con `--dry-run` describe lo que se haria.
+#[derive(Debug, Default, Serialize)]
+pub struct SetupReport {
+    pub dry_run: bool,
+    pub tools: Vec<ToolChange>,
+    pub path_added: Vec<String>,
+    pub path_removed: Vec<String>,
+    pub shortcuts: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plan: Option<Plan>,
+}
+
+/// Accion de `be setup` sobre una herramienta.
+#[derive(Debug, Serialize)]
+pub struct ToolChange {
+    pub tool: String,
+    pub version: String,
+    /// `install`, `upgrade`, `downgrade`, `reinstall` o `skip`.
+    pub action: &'static str,
+    pub from: Option<String>,
+    pub install_dir: PathBuf,
+}
+
+impl ToolChange {
+    fn new(tool: &Tool, action: &installer::InstallAction, target_base: &Path) -> Self {
+        ToolChange {
+            tool: tool.name.clone(),
+            version: tool.version.clone(),
+            action: action.kind(),
+            from: action.from().map(str::to_string),
+            install_dir: installer::install_dir(target_base, tool),
+        }
+    }
+}
+
+pub fn setup_system(options: &SetupOptions) -> Result<SetupReport, BeError> {
     ui::print_banner();
 
     // 1. Prepare Environment
-    let local_app_data = env::var("LOCALAPPDATA")
-        .map_err(|_| BeError::Config("No se encontro %LOCALAPPDATA%".into()))?;
-    let target_base = PathBuf::from(&local_app_data);
+    let target_base = config::tools_root()?;
     ui::print_step(&format!("Ruta Destino: {}", target_base.display()));
 
     // 2. Load Manifest
-    let manifest_path = Path::new("tools.json");
-    let manifest = if manifest_path.exists() {
-        ui::print_step("Cargando tools.json local...");
-        match Manifest::load_from_file(manifest_path) {
-            Ok(m) => m,
-            Err(e) => {
-                ui::print_error(&format!("Error en json local: {}. Usando defaults.", e));
-                Manifest::default()
-            }
+    ui::print_step("Cargando manifiesto (tools.json)...");
+    let manifest = Manifest::load_local_or_default()?;
+
+    // 3. Selection (flags first, Multi-Select Menu only on a terminal)
+    let selected_tools = select_tools(&manifest, options)?;
+
+    if selected_tools.is_empty() {
+        ui::print_error("No seleccionaste nada. Saliendo...");
+        return Ok(SetupReport::default());
+    }
+
+    // 4. Plan (compare receipts with the manifest) + Install Loop
+    let mut plan = Vec::new();
+    for tool_name in &selected_tools {
+        if let Some(tool) = manifest.tools.iter().find(|t| t.name == *tool_name) {
+            let action = installer::plan_install(tool, &target_base)?;
+            plan.push((tool.clone(), action));
         }
-    } else {
-        ui::print_step("Cargando tools.json remoto...");
-        Manifest::default() // En v2.0 forzamos defaults si no hay local, o implementamos fetch
+    }
+    let mut result = SetupReport {
+        dry_run: options.dry_run,
+        tools: plan
+            .iter()
+            .map(|(tool, action)| ToolChange::new(tool, action, &target_base))
+            .collect(),
+        ..Default::default()
     };
+    if options.dry_run {
+        let dry_plan = build_setup_plan(&plan, &target_base)?;
+        dry_plan.print("be setup");
+        result.path_added = dry_plan.path_add.clone();
+        result.path_removed = dry_plan.path_remove.clone();
+        result.shortcuts = dry_plan.shortcuts.clone();
+        result.plan = Some(dry_plan);
+        return Ok(result);
+    }
+    confirm_downgrades(&plan, options)?;
+
+    let mut installed_tools = Vec::new();
+    let mut report = Vec::new();
+    for (tool, action) in plan {
+        installer::install_tool(&tool, &target_base, &action)?;
+        installer::activate(&tool, &action)?;
+        installed_tools.push(tool.clone());
+        report.push((tool, action));
+    }
+
+    // 5. Register in Path & Shortcuts (active versions only)
+    if !installed_tools.is_empty() {
+        let names: Vec<String> = installed_tools.iter().map(|t| t.name.clone()).collect();
+        let diff = register_in_path(&names, "setup")?;
+        result.path_added = diff.added;
+        result.path_removed = diff.removed;
+        let active: Vec<Receipt> = names
+            .iter()
+            .filter_map(|name| Receipt::load_active(name).ok().flatten())
+            .collect();
+        let shortcuts = create_shortcuts(&active)?;
+        record_shortcuts(&shortcuts)?;
+        result.shortcuts = shortcuts.into_iter().map(|(_, link)| link).collect();
+    }
+
+    print_install_report(&report);
+    ui::print_farewell();
+    Ok(result)
+}
+
+fn select_tools(manifest: &Manifest, options: &SetupOptions) -> Result<Vec<String>, BeError> {
+    let tool_names: Vec<&str> = manifest.tools.iter().map(|t| t.name.as_str()).collect();
+
+    if options.all {
+        return Ok(tool_names.iter().map(|n| n.to_string()).collect());
+    }
+    if !options.tools.is_empty() {
+        for name in &options.tools {
+            if !tool_names.contains(&name.as_str()) {
+                return Err(BeError::Config(format!(
+                    "Herramienta desconocida: {}. Disponibles: {}",
+                    name,
+                    tool_names.join(", ")
+                )));
+            }
+        }
+        return Ok(options.tools.clone());
+    }
+    if let Some(profile) = &options.profile {
+        ui::print_step(&format!("Perfil: {}", profile));
+        return manifest.profile_tools(profile);
+    }
+
+    // Default selection comes from the manifest's default profile
+    let default_tools = manifest.default_tools()?;
+    if options.yes {
+        return Ok(default_tools);
+    }
+    if !std::io::stdin().is_terminal() || output::is_json() {
+        return Err(BeError::Config(
+            "No hay una terminal interactiva. Usa --tools <lista>, --all o --yes.".into(),
+        ));
+    }
 
-    // 3. Multi-Select Menu
     ui::print_retro_box(
         "SELECCION DE HERRAMIENTAS",
         &[
@@ -44,194 +189,303 @@ pub fn setup_system() -> Result<(), BeError> {
         ],
     );
 
-    let tool_names: Vec<&str> = manifest.tools.iter().map(|t| t.name.as_str()).collect();
-    // Default selection: Node, MinGW, Git, VSCodium, PowerShell
-    let defaults = vec![0, 1, 2, 3, 4]; // Indexes matching manifest order roughly
+    let defaults: Vec<usize> = tool_names
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| default_tools.iter().any(|t| t == *name))
+        .map(|(i, _)| i)
+        .collect();
 
-    let selected_tools = MultiSelect::new("Herramientas a instalar:", tool_names)
+    let selected = MultiSelect::new("Herramientas a instalar:", tool_names)
         .with_default(&defaults)
         .prompt()
         .map_err(|_| BeError::Cancelled)?;
+    Ok(selected.into_iter().map(str::to_string).collect())
+}
 
-    if selected_tools.is_empty() {
-        ui::print_error("No seleccionaste nada. Saliendo...");
+/// Pide confirmacion antes de instalar versiones mas viejas que las activas.
+/// Con `--yes` o sin terminal se continua sin preguntar.
+fn confirm_downgrades(
+    plan: &[(Tool, installer::InstallAction)],
+    options: &SetupOptions,
+) -> Result<(), BeError> {
+    let downgrades: Vec<String> = plan
+        .iter()
+        .filter(|(_, action)| matches!(action, installer::InstallAction::Downgrade { .. }))
+        .map(|(tool, action)| format!("{} {}", tool.name, action.describe(tool)))
+        .collect();
+    if downgrades.is_empty() || options.yes || !std::io::stdin().is_terminal() || output::is_json()
+    {
         return Ok(());
     }
 
-    // 4. Install Loop
-    let mut installed_tools = Vec::new();
-    for tool_name in selected_tools {
-        if let Some(tool) = manifest.tools.iter().find(|t| t.name == tool_name) {
-            installer::install_tool(tool, &target_base)?;
-            installed_tools.push(tool.clone());
-        }
+    for line in &downgrades {
+        ui::print_step(line);
     }
+    let proceed =
+        Confirm::new("El manifiesto pide versiones anteriores a las activas. ¿Continuar?")
+            .with_default(true)
+            .prompt()
+            .map_err(|_| BeError::Cancelled)?;
+    if proceed {
+        Ok(())
+    } else {
+        Err(BeError::Cancelled)
+    }
+}
 
-    // 5. Register in Path & Shortcuts
-    if !installed_tools.is_empty() {
-        register_in_path(&target_base, &installed_tools)?;
+fn print_install_report(report: &[(Tool, installer::InstallAction)]) {
+    if report.is_empty() {
+        return;
     }
+    let lines: Vec<String> = report
+        .iter()
+        .map(|(tool, action)| format!("{:<10} {}", tool.name, action.describe(tool)))
+        .collect();
+    let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+    ui::print_retro_box("RESUMEN DE CAMBIOS", &lines);
+}
 
-    ui::print_farewell();
+/// Guarda en el recibo activo de cada herramienta los accesos directos creados.
+fn record_shortcuts(shortcuts: &[(String, PathBuf)]) -> Result<(), BeError> {
+    for (tool_name, link) in shortcuts {
+        if let Some(mut receipt) = Receipt::load_active(tool_name)? {
+            if !receipt.shortcuts.contains(link) {
+                receipt.shortcuts.push(link.clone());
+                receipt.save()?;
+            }
+        }
+    }
     Ok(())
 }
 
-fn register_in_path(
+/// Calcula lo que haria `be setup` con la seleccion ya planificada, sin tocar disco ni registro.
+fn build_setup_plan(
+    plan: &[(Tool, installer::InstallAction)],
     target_base: &Path,
-    installed_tools: &[crate::manifest::Tool],
-) -> Result<(), BeError> {
-    ui::print_step("Actualizando Registro (PATH)...");
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let env_key = hkcu
-        .open_subkey_with_flags("Environment", KEY_READ | KEY_WRITE)
-        .map_err(|e| BeError::Setup(format!("Error abriendo registro: {}", e)))?;
+) -> Result<Plan, BeError> {
+    let mut out = Plan::default();
+    let active = ActiveVersions::load()?;
+    let mut upcoming = BTreeMap::new();
+
+    for (tool, action) in plan {
+        let dir = installer::install_dir(target_base, tool);
+        if *action != installer::InstallAction::Skip {
+            out.add_download(tool);
+            if installer::has_legacy_layout(tool, target_base) {
+                out.remove_dirs.push(target_base.join(&tool.name));
+            } else if dir.exists() {
+                out.remove_dirs.push(dir.clone());
+            }
+            if tool.kind != InstallKind::ExeInstaller {
+                out.create_dirs.push(dir.clone());
+            }
+            out.create_dirs.extend(
+                tool.resolved_extra_dirs(&dir)
+                    .into_iter()
+                    .filter(|d| !d.exists()),
+            );
+        }
+        // Same rule as installer::activate
+        if *action != installer::InstallAction::Skip || active.get(&tool.name).is_none() {
+            upcoming.insert(tool.name.clone(), tool.resolved_path_entries(&dir));
+        }
+        if let Some((name, _, _)) = shortcut_spec(&tool.name) {
+            out.shortcuts.extend(desktop_link(name));
+            out.shortcuts.extend(start_menu_link(name));
+        }
+    }
 
-    let current_path: String = env_key.get_value("Path").unwrap_or_default();
-    let mut new_path_parts: Vec<String> = current_path.split(';').map(|s| s.to_string()).collect();
-    let mut changed = false;
+    let names: Vec<String> = plan.iter().map(|(t, _)| t.name.clone()).collect();
+    let (add, remove) = tool_path_changes(&names, &upcoming)?;
+    let store = env_store::user_store()?;
+    let mut path = PathList::new(store.read_path()?, PathStyle::native());
+    let add: Vec<String> = add.iter().map(|e| store.portable_entry(e)).collect();
+    let diff = path.apply(&add, &remove);
+    out.path_add = diff.added;
+    out.path_remove = diff.removed;
+    Ok(out)
+}
 
-    // Define paths based on installed tools
+/// Entradas de PATH a agregar (version activa) y a quitar (otras versiones) por herramienta.
+/// `upcoming` reemplaza las entradas activas de las herramientas que aun no se instalan.
+fn tool_path_changes(
+    tool_names: &[String],
+    upcoming: &BTreeMap<String, Vec<PathBuf>>,
+) -> Result<(Vec<String>, Vec<String>), BeError> {
     let mut paths_to_add = Vec::new();
+    let mut paths_to_remove = Vec::new();
+    for name in tool_names {
+        let active = Receipt::load_active(name)?;
+        let active_version = active.as_ref().map(|r| r.version.clone());
+        match upcoming.get(name) {
+            Some(entries) => paths_to_add.extend(entries.iter().cloned()),
+            None => {
+                if let Some(receipt) = active {
+                    paths_to_add.extend(receipt.path_entries);
+                }
+            }
+        }
+        for other in Receipt::list_versions(name)? {
+            if upcoming.contains_key(name) || Some(&other.version) != active_version.as_ref() {
+                paths_to_remove.extend(other.path_entries);
+            }
+        }
+    }
+    let paths_to_add: Vec<String> = paths_to_add
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let paths_to_remove: Vec<String> = paths_to_remove
+        .iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|p| !paths_to_add.contains(p))
+        .collect();
+    Ok((paths_to_add, paths_to_remove))
+}
 
-    // Always check what is installed
-    if installed_tools.iter().any(|t| t.name == "node") {
-        paths_to_add.push(target_base.join("node").to_string_lossy().to_string());
-    }
-    if installed_tools.iter().any(|t| t.name == "mingw64") {
-        paths_to_add.push(
-            target_base
-                .join("mingw64")
-                .join("bin")
-                .to_string_lossy()
-                .to_string(),
-        );
+/// Aplica los cambios en el almacen y solo escribe (con copia previa) si algo cambio.
+fn update_path(
+    store: &mut dyn EnvStore,
+    history: &PathHistory,
+    add: &[String],
+    remove: &[String],
+    reason: &str,
+) -> Result<PathDiff, BeError> {
+    let mut path = PathList::new(store.read_path()?, PathStyle::native());
+    let add: Vec<String> = add.iter().map(|e| store.portable_entry(e)).collect();
+    let diff = path.apply(&add, remove);
+    let compacted = fit_path_len(store, &mut path);
+    if !diff.is_empty() || compacted {
+        path_history::write_with_backup(store, history, path.entries(), reason)?;
     }
-    if installed_tools.iter().any(|t| t.name == "pwsh") {
-        paths_to_add.push(target_base.join("pwsh").to_string_lossy().to_string());
+    Ok(diff)
+}
+
+/// Cerca del limite del almacen compacta las entradas con `%VAR%`; si aun no alcanza, avisa.
+/// Devuelve `true` si reescribio alguna entrada.
+fn fit_path_len(store: &dyn EnvStore, path: &mut PathList) -> bool {
+    let Some(limit) = store.max_path_len() else {
+        return false;
+    };
+    let near = limit * 9 / 10;
+    let before = path.join(';').len();
+    let compacted = before > near && path.map_entries(|e| store.portable_entry(e));
+    let after = path.join(';').len();
+    if compacted {
+        ui::print_step(&format!(
+            "PATH compactado con variables: {} -> {} caracteres.",
+            before, after
+        ));
     }
-    if installed_tools.iter().any(|t| t.name == "git") {
-        paths_to_add.push(
-            target_base
-                .join("git")
-                .join("bin")
-                .to_string_lossy()
-                .to_string(),
-        );
-        paths_to_add.push(
-            target_base
-                .join("git")
-                .join("cmd")
-                .to_string_lossy()
-                .to_string(),
-        );
+    if after > limit {
+        ui::print_error(&format!(
+            "El PATH del usuario mide {} caracteres (limite {}). Windows puede ignorar las \
+             ultimas entradas: quita las que ya no uses.",
+            after, limit
+        ));
+    } else if after > near {
+        ui::print_step(&format!(
+            "Aviso: el PATH del usuario mide {} de {} caracteres.",
+            after, limit
+        ));
     }
-    if installed_tools.iter().any(|t| t.name == "vscodium") {
-        paths_to_add.push(
-            target_base
-                .join("vscodium")
-                .join("bin")
-                .to_string_lossy()
-                .to_string(),
-        );
+    compacted
+}
+
+/// Deja en el PATH del usuario las entradas de la version activa de cada herramienta
+/// y quita las de sus otras versiones instaladas.
+fn register_in_path(tool_names: &[String], reason: &str) -> Result<PathDiff, BeError> {
+    let mut store = env_store::user_store()?;
+    ui::print_step(&format!("Actualizando PATH ({})...", store.location()));
+    let (paths_to_add, paths_to_remove) = tool_path_changes(tool_names, &BTreeMap::new())?;
+
+    let history = PathHistory::open()?;
+    let diff = update_path(
+        store.as_mut(),
+        &history,
+        &paths_to_add,
+        &paths_to_remove,
+        reason,
+    )?;
+
+    for p in &diff.removed {
+        ui::print_step(&format!("Quitando del PATH: {}", p));
     }
-    if installed_tools.iter().any(|t| t.name == "rustup") {
-        if let Ok(home) = env::var("USERPROFILE") {
-            paths_to_add.push(format!("{}\\.cargo\\bin", home));
-        }
+    for p in &diff.added {
+        ui::print_step(&format!("Anadiendo al PATH: {}", p));
     }
 
-    for p in paths_to_add {
-        if !new_path_parts.contains(&p) {
-            new_path_parts.push(p.clone());
-            ui::print_step(&format!("Anadiendo al PATH: {}", p));
-            changed = true;
-        }
+    if diff.is_empty() {
+        ui::print_success("El PATH ya estaba correcto.");
+    } else {
+        ui::print_success("PATH actualizado.");
     }
 
-    if changed {
-        let new_path_str = new_path_parts.join(";");
-        env_key
-            .set_value("Path", &new_path_str)
-            .map_err(|e| BeError::Setup(format!("Error escribiendo registro: {}", e)))?;
-        ui::print_success("Registro actualizado.");
-    } else {
-        ui::print_success("El PATH ya estaba correcto.");
+    Ok(diff)
+}
+
+/// Accesos directos conocidos: `(nombre del .lnk, ejecutable, descripcion)`.
+/// Solo en Windows: los `.lnk` se crean con PowerShell y WScript.
+fn shortcut_spec(tool_name: &str) -> Option<(&'static str, &'static str, &'static str)> {
+    if !cfg!(windows) {
+        return None;
     }
+    match tool_name {
+        "pwsh" => Some(("PowerShell Portable", "pwsh.exe", "PowerShell 7")),
+        "vscodium" => Some(("VSCodium Portable", "VSCodium.exe", "VSCodium Editor")),
+        "git" => Some(("Git Bash Portable", "git-bash.exe", "Git Terminal")),
+        _ => None,
+    }
+}
 
-    // Shortcuts
-    create_shortcuts(target_base, installed_tools)?;
+fn desktop_link(name: &str) -> Option<PathBuf> {
+    dirs::desktop_dir().map(|d| d.join(format!("{}.lnk", name)))
+}
 
-    Ok(())
+/// El Menu Inicio solo se usa si la carpeta existe.
+fn start_menu_link(name: &str) -> Option<PathBuf> {
+    dirs::data_dir()
+        .map(|d| d.join("Microsoft/Windows/Start Menu/Programs"))
+        .filter(|p| p.exists())
+        .map(|p| p.join(format!("{}.lnk", name)))
 }
 
-fn create_shortcuts(
-    target_base: &Path,
-    installed_tools: &[crate::manifest::Tool],
-) -> Result<(), BeError> {
-    let desktop = dirs::desktop_dir().ok_or(BeError::Setup("No Desktop".into()))?;
-
-    // START MENU
-    let start_menu = dirs::data_dir().map(|d| d.join("Microsoft/Windows/Start Menu/Programs"));
-
-    for tool in installed_tools {
-        let (name, target, desc) = match tool.name.as_str() {
-            "pwsh" => (
-                "PowerShell Portable".to_string(),
-                "pwsh.exe",
-                "PowerShell 7",
-            ),
-            "vscodium" => (
-                "VSCodium Portable".to_string(),
-                "VSCodium.exe",
-                "VSCodium Editor",
-            ),
-            "git" => (
-                "Git Bash Portable".to_string(),
-                "git-bash.exe",
-                "Git Terminal",
-            ),
-            _ => continue,
-        };
+/// Crea los accesos directos y devuelve `(herramienta, ruta del .lnk)` de los creados.
+fn create_shortcuts(installed: &[Receipt]) -> Result<Vec<(String, PathBuf)>, BeError> {
+    let mut created = Vec::new();
 
-        // Determine real target path
-        let real_target = if tool.name == "git" {
-            target_base.join(&tool.name).join(target) // Git has it in root but sometimes not
-        } else {
-            target_base.join(&tool.name).join(target)
+    for receipt in installed {
+        let Some((name, target, desc)) = shortcut_spec(&receipt.tool) else {
+            continue;
         };
-        // Git Bash special case: it might be in root of git folder
 
-        let link_path = desktop.join(format!("{}.lnk", name));
+        // Shortcuts always point to the installed version folder
+        let real_target = receipt.install_dir.join(target);
 
-        create_shortcut_impl(
-            target_base,
-            &link_path,
-            &real_target.to_string_lossy(),
-            desc,
-        )?;
+        match desktop_link(name) {
+            Some(link_path) => {
+                if create_shortcut_impl(&link_path, &real_target.to_string_lossy(), desc)? {
+                    created.push((receipt.tool.clone(), link_path));
+                }
+            }
+            None => ui::print_warning(&format!(
+                "No se encontro el Escritorio: no se crea el acceso directo '{}'.",
+                name
+            )),
+        }
 
         // Try start menu
-        if let Some(ref start) = start_menu {
-            if start.exists() {
-                let sm_link = start.join(format!("{}.lnk", name));
-                let _ = create_shortcut_impl(
-                    target_base,
-                    &sm_link,
-                    &real_target.to_string_lossy(),
-                    desc,
-                );
+        if let Some(sm_link) = start_menu_link(name) {
+            if let Ok(true) = create_shortcut_impl(&sm_link, &real_target.to_string_lossy(), desc) {
+                created.push((receipt.tool.clone(), sm_link));
             }
         }
     }
-    Ok(())
+    Ok(created)
 }
 
-fn create_shortcut_impl(
-    _target_base: &Path,
-    link_path: &Path,
-    target_exe: &str,
-    desc: &str,
-) -> Result<(), BeError> {
+fn create_shortcut_impl(link_path: &Path, target_exe: &str, desc: &str) -> Result<bool, BeError> {
     let script = format!(
         "$ws = New-Object -ComObject WScript.Shell; \
          $s = $ws.CreateShortcut('{}'); \
@@ -249,6 +503,7 @@ fn create_shortcut_impl(
         .arg("-NoProfile")
         .arg("-Command")
         .arg(&script)
+        .stdout(output::child_stdout())
         .status()
         .map_err(|e| BeError::Setup(format!("Error PS Shortcut: {}", e)))?;
 
@@ -259,142 +514,339 @@ fn create_shortcut_impl(
         ));
     }
 
-    Ok(())
+    Ok(status.success())
 }
 
-pub fn clean_system() -> Result<(), BeError> {
-    println!("🧹 Limpiando Entorno Brisas del Sistema...");
-    info!("Iniciando clean_system...");
+/// `be use <tool> [version]`: sin version lista las instaladas; con version cambia la activa
+/// sin descargar nada y actualiza PATH y accesos directos.
+pub fn use_version(tool_name: &str, requested: Option<&str>) -> Result<(), BeError> {
+    let installed = Receipt::list_versions(tool_name)?;
+    if installed.is_empty() {
+        return Err(BeError::Config(format!(
+            "{} no esta instalado. Ejecuta 'be setup' primero.",
+            tool_name
+        )));
+    }
+    let mut active = ActiveVersions::load()?;
 
-    let local_app_data = env::var("LOCALAPPDATA")
-        .map_err(|_| BeError::Config("No se encontró %LOCALAPPDATA%".into()))?;
-    let target_base = PathBuf::from(&local_app_data);
+    let Some(requested) = requested else {
+        println!("Versiones instaladas de {}:", tool_name);
+        for receipt in &installed {
+            let marker = if active.get(tool_name) == Some(receipt.version.as_str()) {
+                "*"
+            } else {
+                " "
+            };
+            println!("  {} {}", marker, receipt.version);
+        }
+        return Ok(());
+    };
 
-    let tools = vec!["node", "mingw64", "pwsh"];
+    // Exact match first, then the newest version matching the prefix (e.g. "18")
+    let receipt = installed
+        .iter()
+        .find(|r| r.version == requested)
+        .or_else(|| {
+            installed
+                .iter()
+                .rev()
+                .find(|r| version::matches(requested, &r.version))
+        })
+        .ok_or_else(|| {
+            let available: Vec<&str> = installed.iter().map(|r| r.version.as_str()).collect();
+            BeError::Config(format!(
+                "{} {} no esta instalado. Disponibles: {}",
+                tool_name,
+                requested,
+                available.join(", ")
+            ))
+        })?;
+
+    active.set(tool_name, &receipt.version);
+    active.save()?;
+    info!("Version activa de {}: {}", tool_name, receipt.version);
+
+    register_in_path(&[tool_name.to_string()], "use")?;
+    let shortcuts = create_shortcuts(std::slice::from_ref(receipt))?;
+    record_shortcuts(&shortcuts)?;
+
+    ui::print_success(&format!("{} {} activo.", tool_name, receipt.version));
+    Ok(())
+}
 
-    // 2. Eliminar Archivos (Instalación)
-    for tool in &tools {
-        let path = target_base.join(tool);
-        if path.exists() {
-            println!("  Eliminando carpeta: {}", path.display());
-            if let Err(e) = fs::remove_dir_all(&path) {
-                error!("Fallo al eliminar directorio {}: {}", path.display(), e);
-                eprintln!("Error eliminando {}: {}", tool, e);
-            } else {
-                info!("Directorio eliminado: {}", path.display());
-                println!("    Eliminado.");
+/// Lo que `be clean` eliminaria segun los recibos: carpetas de cada version, carpetas creadas
+/// fuera de ellas, accesos directos y entradas de PATH. Sin herramienta concreta tambien se
+/// borran la cache de descargas, los temporales y `BrisasEnv` (recibos y `be.log`; se
+/// conservan `config.toml` y el historial del PATH).
+fn plan_clean(
+    target_base: &Path,
+    tool_filter: Option<&str>,
+    current_path: &PathList,
+) -> Result<Plan, BeError> {
+    let manifest = Manifest::load_local_or_default()?;
+    let mut names = receipt::installed_tools()?;
+    // Installs made before receipts existed only left their folder behind
+    for tool in &manifest.tools {
+        if installer::has_legacy_layout(tool, target_base) && !names.contains(&tool.name) {
+            names.push(tool.name.clone());
+        }
+    }
+    if let Some(tool_name) = tool_filter {
+        if !names.iter().any(|n| n == tool_name) {
+            return Err(BeError::Config(format!("{} no esta instalado.", tool_name)));
+        }
+        names = vec![tool_name.to_string()];
+    }
+
+    let mut plan = Plan::default();
+    let mut entries: Vec<PathBuf> = Vec::new();
+    for name in &names {
+        let tool_dir = target_base.join(name);
+        if tool_dir.exists() {
+            plan.remove_dirs.push(tool_dir.clone());
+        }
+        for receipt in Receipt::list_versions(name)? {
+            if !receipt.install_dir.starts_with(&tool_dir) && receipt.install_dir.exists() {
+                plan.remove_dirs.push(receipt.install_dir.clone());
+            }
+            plan.remove_dirs
+                .extend(receipt.created_dirs.iter().filter(|d| d.exists()).cloned());
+            plan.remove_files
+                .extend(receipt.shortcuts.iter().filter(|l| l.exists()).cloned());
+            entries.extend(receipt.path_entries);
+        }
+        if let Some(tool) = manifest.tools.iter().find(|t| t.name == *name) {
+            if installer::has_legacy_layout(tool, target_base) {
+                entries.extend(tool.resolved_path_entries(&tool_dir));
+            }
+        }
+        // Shortcuts from installs that did not record them yet
+        if let Some((link_name, _, _)) = shortcut_spec(name) {
+            for link in desktop_link(link_name)
+                .into_iter()
+                .chain(start_menu_link(link_name))
+            {
+                if link.exists() && !plan.remove_files.contains(&link) {
+                    plan.remove_files.push(link);
+                }
             }
         }
     }
 
-    // 2.1 Eliminar Cache de Descargas
-    let cache_dir = std::env::temp_dir().join("BrisasEnv_Cache");
-    if cache_dir.exists() {
-        println!("  Borrando cache de descargas: {}", cache_dir.display());
-        if let Err(e) = fs::remove_dir_all(&cache_dir) {
-            eprintln!("Error eliminando cache: {}", e);
-        } else {
-            println!("    Cache eliminado.");
+    if tool_filter.is_none() {
+        // BrisasEnv except the PATH history, so the clean itself can be undone, and the
+        // user's config.toml
+        let state_dir = config::state_dir()?;
+        // The default cache lives in BrisasEnv and goes with it; a custom one may be a
+        // shared folder, so only the downloads Brisas recorded are removed
+        if !cache::cache_dir()?.starts_with(&state_dir) {
+            let (dirs, files) = DownloadCache::open()?.owned_paths()?;
+            plan.remove_dirs.extend(dirs);
+            plan.remove_files.extend(files);
+        }
+        for dir in [cache::legacy_cache_dir(), admin::hash_calc_dir()] {
+            if dir.exists() {
+                plan.remove_dirs.push(dir);
+            }
+        }
+        let keep = [path_history::dir()?, config::settings_file()?];
+        if state_dir.exists() {
+            for entry in fs::read_dir(&state_dir)? {
+                let path = entry?.path();
+                if keep.contains(&path) || plan.remove_dirs.contains(&path) {
+                    continue;
+                }
+                if path.is_dir() {
+                    plan.remove_dirs.push(path);
+                } else {
+                    plan.remove_files.push(path);
+                }
+            }
         }
     }
+    // Receipts of different versions may record the same folders: keep the first mention
+    let mut seen = HashSet::new();
+    plan.remove_dirs.retain(|dir| seen.insert(dir.clone()));
+    plan.remove_files.retain(|file| seen.insert(file.clone()));
+
+    let entries: Vec<String> = entries
+        .iter()
+        .map(|e| e.to_string_lossy().to_string())
+        .collect();
+    plan.path_remove = current_path.clone().apply(&[], &entries).removed;
+    Ok(plan)
+}
+
+/// Resultado de `be clean`; con `--dry-run` describe lo que se borraria.
+#[derive(Debug, Default, Serialize)]
+pub struct CleanReport {
+    pub dry_run: bool,
+    pub tool: Option<String>,
+    pub path_removed: Vec<String>,
+    pub removed_dirs: Vec<PathBuf>,
+    pub removed_files: Vec<PathBuf>,
+    /// Lo que no se pudo borrar (ej. archivos en uso).
+    pub failed: Vec<FailedRemoval>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FailedRemoval {
+    pub path: PathBuf,
+    pub error: String,
+}
 
-    // 3. Limpiar Registro
-    println!("Limpiando Registro de Usuario (PATH)...");
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    // Usar open_subkey_with_flags
-    let env_key = hkcu
-        .open_subkey_with_flags("Environment", KEY_READ | KEY_WRITE)
-        .map_err(|e| BeError::Setup(format!("Error abriendo registro: {}", e)))?;
+/// `be clean [tool]`: desinstala todo lo que registraron los recibos (o solo una herramienta).
+pub fn clean_system(tool_filter: Option<&str>, dry_run: bool) -> Result<CleanReport, BeError> {
+    say!("🧹 Limpiando Entorno Brisas del Sistema...");
+    info!("Iniciando clean_system...");
+
+    let target_base = config::tools_root()?;
 
-    let current_path: String = match env_key.get_value("Path") {
-        Ok(val) => val,
+    let mut store = env_store::user_store()?;
+    let current_path = match store.read_path() {
+        Ok(val) => PathList::new(val, PathStyle::native()),
         Err(e) => {
-            println!("Advertencia: No se pudo leer el PATH actual: {}", e);
-            String::new()
+            ui::print_error(&format!("No se pudo leer el PATH actual: {}", e));
+            PathList::new(Vec::new(), PathStyle::native())
         }
     };
-    let parts: Vec<&str> = current_path.split(';').collect();
-
-    let paths_to_remove = [
-        target_base.join("node").to_string_lossy().to_string(),
-        target_base
-            .join("mingw64")
-            .join("bin")
-            .to_string_lossy()
-            .to_string(),
-        target_base.join("pwsh").to_string_lossy().to_string(),
-    ];
-
-    let new_parts: Vec<&str> = parts
-        .into_iter()
-        .filter(|part| {
-            !part.is_empty() && !paths_to_remove.iter().any(|remove| part.contains(remove))
-        })
-        .collect();
+    let plan = plan_clean(&target_base, tool_filter, &current_path)?;
+    let mut report = CleanReport {
+        dry_run,
+        tool: tool_filter.map(str::to_string),
+        ..Default::default()
+    };
+    if dry_run {
+        plan.print("be clean");
+        report.path_removed = plan.path_remove;
+        report.removed_dirs = plan.remove_dirs;
+        report.removed_files = plan.remove_files;
+        return Ok(report);
+    }
 
-    let new_path_str = new_parts.join(";");
+    // 1. Limpiar PATH (antes de borrar los recibos que lo describen)
+    say!("Limpiando PATH de Usuario ({})...", store.location());
+    let mut new_path = current_path.clone();
+    let diff = new_path.apply(&[], &plan.path_remove);
 
-    if new_path_str.len() < 5 && !current_path.is_empty() {
-        println!("Advertencia: El PATH resultante parece muy corto. Abortando actualizacion.");
-        return Ok(());
+    if store.holds_full_path() && new_path.join(';').len() < 5 && !current_path.entries().is_empty()
+    {
+        return Err(BeError::Setup(
+            "El PATH resultante parece muy corto. Abortando actualizacion.".into(),
+        ));
     }
 
-    if new_path_str != current_path {
-        env_key
-            .set_value("Path", &new_path_str)
-            .map_err(|e| BeError::Setup(format!("Error guardando registro: {}", e)))?;
-        println!("Registro limpiado correctamente.");
-        println!("Nota: Reinicia tus terminales para ver los cambios.");
-        info!("Registro limpiado exitosamente.");
+    if !diff.is_empty() {
+        let history = PathHistory::open()?;
+        path_history::write_with_backup(store.as_mut(), &history, new_path.entries(), "clean")?;
+        say!("PATH limpiado correctamente.");
+        say!("Nota: Reinicia tus terminales para ver los cambios.");
+        info!("PATH limpiado exitosamente.");
     } else {
-        println!("El registro ya estaba limpio.");
+        say!("El PATH ya estaba limpio.");
     }
-    Ok(())
-}
 
-pub fn check_status() {
-    println!("Verificando Estado del Sistema...");
-
-    let tools = vec![
-        ("node", "--version"),
-        ("gcc", "--version"),
-        ("pwsh", "--version"),
-        ("git", "--version"),
-        ("git-lfs", "--version"),
-        ("rustc", "--version"),
-        ("cargo", "--version"),
-    ];
-
-    println!("\nPrueba de Ejecución (Detecta instalaciones globales y portables):");
-    let mut all_ok = true;
-
-    for (cmd, arg) in tools {
-        match std::process::Command::new(cmd).arg(arg).output() {
-            Ok(output) if output.status.success() => {
-                let version = String::from_utf8_lossy(&output.stdout)
-                    .lines()
-                    .next()
-                    .unwrap_or("Detected")
-                    .to_string();
-                let v_short = if version.len() > 25 {
-                    &version[..25]
-                } else {
-                    &version
-                };
-                println!("  [x] {:<10} : Funcionando ({})", cmd, v_short.trim());
-            }
-            _ => {
-                println!("  [ ] {:<10} : No detectado en PATH actual", cmd);
-                all_ok = false;
+    // 2. Eliminar Accesos Directos y archivos sueltos (be.log, active.json)
+    for link in &plan.remove_files {
+        say!("  Eliminando: {}", link.display());
+        match fs::remove_file(link) {
+            Ok(()) => report.removed_files.push(link.clone()),
+            Err(e) => {
+                error!("Fallo al eliminar {}: {}", link.display(), e);
+                eprintln!("Error eliminando {}: {}", link.display(), e);
+                report.failed.push(FailedRemoval {
+                    path: link.clone(),
+                    error: e.to_string(),
+                });
             }
         }
     }
 
-    println!("\nNota: Si acabas de instalar, reinicia tu terminal para recargar el PATH.");
+    // 3. Eliminar Archivos (Instalación, cache, temporales y estado)
+    for path in &plan.remove_dirs {
+        say!("  Eliminando carpeta: {}", path.display());
+        if let Err(e) = fs::remove_dir_all(path) {
+            error!("Fallo al eliminar directorio {}: {}", path.display(), e);
+            eprintln!("Error eliminando {}: {}", path.display(), e);
+            report.failed.push(FailedRemoval {
+                path: path.clone(),
+                error: e.to_string(),
+            });
+        } else {
+            info!("Directorio eliminado: {}", path.display());
+            say!("    Eliminado.");
+            report.removed_dirs.push(path.clone());
+        }
+    }
 
-    if all_ok {
-        println!("\n¡Sistema Operativo al 100%! 🚀");
-    } else {
-        println!("\nAlgunas herramientas no responden. Verifica tu instalación.");
+    // 4. Olvidar la herramienta (sin filtro, BrisasEnv ya se borro entero)
+    if let Some(tool_name) = tool_filter {
+        Receipt::delete_all(tool_name)?;
+        let mut active = ActiveVersions::load()?;
+        active.remove(tool_name);
+        active.save()?;
+        ui::print_success(&format!("{} desinstalado.", tool_name));
+    } else if !diff.is_empty() {
+        say!("El PATH anterior quedo guardado: 'be path restore' deshace el cambio.");
+    }
+    report.path_removed = diff.removed;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env_store::MemoryStore;
+
+    #[test]
+    fn test_update_path_writes_only_on_change() {
+        let mut store = MemoryStore {
+            path: vec!["C:\\Windows".to_string(), "C:\\be\\git\\cmd".to_string()],
+            ..Default::default()
+        };
+        let git = vec!["C:\\be\\git\\cmd".to_string()];
+
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let history = PathHistory::at(dir.path().to_path_buf());
+
+        let diff = update_path(&mut store, &history, &git, &[], "setup").unwrap();
+        assert!(diff.is_empty());
+        assert_eq!(store.writes, 0);
+        assert!(history.list().unwrap().is_empty());
+
+        let diff = update_path(&mut store, &history, &[], &git, "use").unwrap();
+        assert_eq!(diff.removed, git);
+        assert_eq!(store.path, vec!["C:\\Windows"]);
+        assert_eq!(store.writes, 1);
+        assert_eq!(history.find(None).unwrap().entries.len(), 2);
+    }
+
+    #[test]
+    fn test_update_path_stores_entries_with_variables_and_compacts() {
+        let local = "C:\\Users\\Ana\\AppData\\Local";
+        let mut store = MemoryStore {
+            path: vec![
+                format!("{}\\Programs\\Python\\Python312", local),
+                "C:\\Windows".to_string(),
+            ],
+            vars: vec![("LOCALAPPDATA".to_string(), local.to_string())],
+            max_len: Some(90),
+            ..Default::default()
+        };
+        let dir = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+        let history = PathHistory::at(dir.path().to_path_buf());
+
+        let node = format!("{}\\node\\22.12.0", local);
+        let diff = update_path(&mut store, &history, &[node], &[], "setup").unwrap();
+        assert_eq!(diff.added, vec!["%LOCALAPPDATA%\\node\\22.12.0"]);
+        // Over 90% of the limit: existing entries are compacted too
+        assert_eq!(
+            store.path,
+            vec![
+                "%LOCALAPPDATA%\\Programs\\Python\\Python312",
+                "C:\\Windows",
+                "%LOCALAPPDATA%\\node\\22.12.0",
+            ]
+        );
     }
 }
@@ -1,6 +1,10 @@
+use crate::output;
 use console::{style, Term};
 
 pub fn print_banner() {
+    if output::is_json() {
+        return;
+    }
     let term = Term::stdout();
     let _ = term.clear_screen();
 
@@ -26,19 +30,51 @@ pub fn print_banner() {
     println!();
 }
 
+/// Linea para personas: a stderr si `be env` esta generando un script en stdout.
+fn print_line(line: String) {
+    if output::is_script() {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
 pub fn print_step(msg: &str) {
-    println!(" {} {}", style(">>").green().bold(), msg);
+    if output::is_json() {
+        return;
+    }
+    print_line(format!(" {} {}", style(">>").green().bold(), msg));
 }
 
 pub fn print_success(msg: &str) {
-    println!(" {} {}", style("OK").green().bold(), msg);
+    if output::is_json() {
+        return;
+    }
+    print_line(format!(" {} {}", style("OK").green().bold(), msg));
 }
 
+/// Con `--output json` va a stderr, como `print_error`.
+pub fn print_warning(msg: &str) {
+    if output::is_json() {
+        eprintln!("AVISO {}", msg);
+        return;
+    }
+    print_line(format!(" {} {}", style("AVISO").yellow().bold(), msg));
+}
+
+/// Con `--output json` va a stderr: stdout queda solo para el JSON.
 pub fn print_error(msg: &str) {
-    println!(" {} {}", style("ERROR").red().bold(), msg);
+    if output::is_json() {
+        eprintln!("ERROR {}", msg);
+        return;
+    }
+    print_line(format!(" {} {}", style("ERROR").red().bold(), msg));
 }
 
 pub fn print_retro_box(title: &str, content: &[&str]) {
+    if output::is_json() {
+        return;
+    }
     let width = 60;
     let title_len = title.len();
     let _padding = (width - title_len) / 2;
@@ -53,6 +89,9 @@ pub fn print_retro_box(title: &str, content: &[&str]) {
 }
 
 pub fn print_farewell() {
+    if output::is_json() {
+        return;
+    }
     println!();
     let msg = r#"
     ¡Gracias por usar Brisas CLI! 
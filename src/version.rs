@@ -0,0 +1,68 @@
+use std::cmp::Ordering;
+
+/// Compara dos versiones tipo `22.12.0`, `v1.96.0` o `2.47.1.windows.1`.
+/// Los segmentos numericos se comparan como numeros y el resto como texto.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    let left = segments(a);
+    let right = segments(b);
+
+    for (l, r) in left.iter().zip(right.iter()) {
+        let ord = match (l.parse::<u64>(), r.parse::<u64>()) {
+            (Ok(l), Ok(r)) => l.cmp(&r),
+            _ => l.cmp(r),
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    left.len().cmp(&right.len())
+}
+
+/// `true` si `requested` coincide con `version` completa o con sus primeros segmentos
+/// (ej. `18` y `18.20` coinciden con `18.20.4`, pero `1` no coincide con `18.0.0`).
+pub fn matches(requested: &str, version: &str) -> bool {
+    let requested = segments(requested);
+    let version = segments(version);
+    !requested.is_empty()
+        && requested.len() <= version.len()
+        && requested.iter().zip(version.iter()).all(|(r, v)| r == v)
+}
+
+fn segments(version: &str) -> Vec<&str> {
+    let trimmed = version.trim();
+    let trimmed = trimmed
+        .strip_prefix('v')
+        .or_else(|| trimmed.strip_prefix('V'))
+        .unwrap_or(trimmed);
+    trimmed
+        .split(['.', '-', '+'])
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_numeric_segments() {
+        assert_eq!(compare("22.12.0", "22.9.1"), Ordering::Greater);
+        assert_eq!(compare("18.20.4", "22.12.0"), Ordering::Less);
+        assert_eq!(compare("v1.2.3", "1.2.3"), Ordering::Equal);
+        assert_eq!(compare("1.2", "1.2.1"), Ordering::Less);
+        assert_eq!(
+            compare("2.47.1.windows.2", "2.47.1.windows.1"),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_matches_prefix_segments() {
+        assert!(matches("18", "18.20.4"));
+        assert!(matches("v18.20", "18.20.4"));
+        assert!(matches("22.12.0", "22.12.0"));
+        assert!(!matches("1", "18.0.0"));
+        assert!(!matches("18.20.4.1", "18.20.4"));
+        assert!(!matches("", "18.20.4"));
+    }
+}
@@ -3,8 +3,10 @@ use predicates::prelude::*;
 
 #[test]
 fn test_version() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
-    cmd.arg("--version")
+    cmd.env("LOCALAPPDATA", local.path())
+        .arg("--version")
         .assert()
         .success()
         .stdout(predicate::str::contains("be 2.0.0"));
@@ -12,8 +14,10 @@ fn test_version() {
 
 #[test]
 fn test_help() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
-    cmd.arg("help")
+    cmd.env("LOCALAPPDATA", local.path())
+        .arg("help")
         .assert()
         .success()
         .stdout(predicate::str::contains("MANUAL DE USUARIO"));
@@ -21,14 +25,382 @@ fn test_help() {
 
 #[test]
 fn test_status_fails_clean() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
-    cmd.env("LOCALAPPDATA", "C:\\FakePathThatDoesNotExist")
-        .arg("status")
+    cmd.env(
+        "LOCALAPPDATA",
+        local.path().join("FakePathThatDoesNotExist"),
+    )
+    .arg("status")
+    .assert()
+    .success() // Deberia salir con 0, pero mostrar error en output
+    .stdout(
+        predicate::str::contains("No se encontro %LOCALAPPDATA%")
+            .or(predicate::str::contains("Hay inconsistencias"))
+            .or(predicate::str::contains("No encontrado")),
+    );
+}
+
+#[test]
+fn test_setup_without_tty_requires_selection() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .arg("setup")
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--tools"));
+}
+
+#[test]
+fn test_setup_rejects_unknown_tool() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .args(["setup", "--tools", "node,noexiste"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Herramienta desconocida: noexiste",
+        ));
+}
+
+#[test]
+fn test_clean_tool_dry_run_lists_receipt_without_deleting() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let install_dir = local.path().join("git").join("2.47.1");
+    std::fs::create_dir_all(&install_dir).expect("Fallo al crear carpeta de instalacion");
+    let receipts = local.path().join("BrisasEnv").join("receipts").join("git");
+    std::fs::create_dir_all(&receipts).expect("Fallo al crear carpeta de recibos");
+    let receipt = serde_json::json!({
+        "tool": "git",
+        "version": "2.47.1",
+        "url": "https://example.com/git.7z.exe",
+        "sha256": null,
+        "installed_at": "2024-01-01 00:00:00",
+        "install_dir": install_dir,
+    });
+    std::fs::write(receipts.join("2.47.1.json"), receipt.to_string())
+        .expect("Fallo al escribir recibo");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .args(["clean", "git", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            local.path().join("git").display().to_string(),
+        ))
+        .stdout(predicate::str::contains("Nada se modifico"));
+    assert!(install_dir.exists());
+}
+
+#[test]
+fn test_clean_plan_lists_shared_dirs_once() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let shared = local.path().join("git-extra");
+    std::fs::create_dir_all(&shared).expect("Fallo al crear carpeta compartida");
+    let receipts = local.path().join("BrisasEnv").join("receipts").join("git");
+    std::fs::create_dir_all(&receipts).expect("Fallo al crear carpeta de recibos");
+    // 2.47.1 lives outside <root>/git, so its install_dir lands between the two mentions
+    let install_dirs = [
+        local.path().join("git").join("2.47.0"),
+        local.path().join("git-2.47.1"),
+    ];
+    for (version, install_dir) in ["2.47.0", "2.47.1"].iter().zip(&install_dirs) {
+        std::fs::create_dir_all(install_dir).expect("Fallo al crear carpeta de instalacion");
+        let receipt = serde_json::json!({
+            "tool": "git",
+            "version": version,
+            "url": "https://example.com/git.7z.exe",
+            "sha256": null,
+            "installed_at": "2024-01-01 00:00:00",
+            "install_dir": install_dir,
+            "created_dirs": [shared],
+        });
+        std::fs::write(
+            receipts.join(format!("{}.json", version)),
+            receipt.to_string(),
+        )
+        .expect("Fallo al escribir recibo");
+    }
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    let output = cmd
+        .env("LOCALAPPDATA", local.path())
+        .args(["clean", "git", "--dry-run", "--output", "json"])
+        .output()
+        .expect("Fallo al ejecutar be");
+    assert!(output.status.success());
+    let value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout debe ser solo JSON");
+    let dirs = value["result"]["removed_dirs"].as_array().unwrap();
+    let shared = serde_json::json!(shared);
+    assert_eq!(dirs.iter().filter(|d| **d == shared).count(), 1);
+}
+
+#[test]
+fn test_clean_rejects_tool_not_installed() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .args(["clean", "node", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("node no esta instalado"));
+}
+
+#[test]
+fn test_path_restore_without_history_fails() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .args(["path", "restore"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No hay copias del PATH"));
+}
+
+#[test]
+fn test_doctor_suggests_setup_for_missing_tools() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .env("HOME", local.path())
+        .env("PATH", local.path())
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "No instalado: 'be setup --tools node'.",
+        ));
+}
+
+#[test]
+fn test_doctor_reports_broken_manifest() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    std::fs::write(local.path().join("tools.json"), "{ roto").expect("Fallo al escribir");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    let output = cmd
+        .env("LOCALAPPDATA", local.path())
+        .current_dir(local.path())
+        .args(["doctor", "--output", "json"])
+        .output()
+        .expect("Fallo al ejecutar be");
+    let value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout debe ser solo JSON");
+    assert_eq!(value["result"]["healthy"], false);
+    assert!(value["result"]["manifest_error"]
+        .as_str()
+        .is_some_and(|e| e.contains("JSON")));
+    assert_eq!(value["result"]["tools"], serde_json::json!([]));
+}
+
+#[test]
+fn test_status_json_output() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    let output = cmd
+        .env("LOCALAPPDATA", local.path())
+        .env("HOME", local.path())
+        .env("PATH", local.path())
+        .args(["status", "--output", "json"])
+        .output()
+        .expect("Fallo al ejecutar be");
+    assert!(output.status.success());
+
+    let value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout debe ser solo JSON");
+    assert_eq!(value["command"], "status");
+    assert_eq!(value["ok"], true);
+    assert_eq!(value["result"]["healthy"], false);
+    assert_eq!(value["result"]["tools"][0]["source"], "not-found");
+}
+
+#[test]
+fn test_json_error_has_code() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    let output = cmd
+        .env("LOCALAPPDATA", local.path())
+        .args(["--output", "json", "clean", "noexiste"])
+        .output()
+        .expect("Fallo al ejecutar be");
+    assert!(!output.status.success());
+
+    let value: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("stdout debe ser solo JSON");
+    assert_eq!(value["ok"], false);
+    assert_eq!(value["error"]["code"], "config");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_run_propagates_exit_code() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .current_dir(local.path())
+        .args(["run", "sh", "-c", "exit 3"])
+        .assert()
+        .code(3);
+}
+
+#[test]
+fn test_run_fails_on_broken_manifest() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    std::fs::write(local.path().join("tools.json"), "{ roto").expect("Fallo al escribir");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .current_dir(local.path())
+        .args(["run", "comando-que-no-existe"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error de JSON"));
+}
+
+#[test]
+fn test_setup_fails_on_broken_manifest() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    std::fs::write(local.path().join("tools.json"), "{ roto").expect("Fallo al escribir");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .current_dir(local.path())
+        .args(["setup", "--all", "--yes", "--dry-run"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Error de JSON"));
+}
+
+#[test]
+fn test_run_reports_missing_command() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .current_dir(local.path())
+        .args(["run", "comando-que-no-existe"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains(
+            "No se encontro el comando 'comando-que-no-existe'",
+        ));
+}
+
+#[test]
+fn test_cache_import_rejects_unknown_file() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let file = local.path().join("node-v0.0.0-win-x64.zip");
+    std::fs::write(&file, b"no es node").unwrap();
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .env("BRISAS_CACHE_DIR", local.path().join("cache"))
+        .args(["cache", "import"])
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Ningun sha256 del manifiesto coincide",
+        ));
+    assert!(!local.path().join("cache").exists());
+}
+
+#[test]
+fn test_env_keeps_install_messages_out_of_the_script() {
+    let project = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    std::fs::write(
+        project.path().join("tools.json"),
+        r#"{"tools":[{"name":"hello","version":"1.0","url":"unsupported://example/hello.tar.gz",
+            "check_file":"bin/hello","kind":"tar-gz","path_entries":["{dir}/bin"]}]}"#,
+    )
+    .unwrap();
+    std::fs::write(
+        project.path().join(".brisas.toml"),
+        "[tools]\nhello = \"1.0\"\n",
+    )
+    .unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    let output = cmd
+        .current_dir(project.path())
+        .env("LOCALAPPDATA", project.path().join("local"))
+        .env("HOME", project.path())
+        .args(["env", "--shell", "bash"])
+        .output()
+        .expect("Fallo al ejecutar be");
+    // The install fails, but nothing it printed may end up in `eval`
+    assert!(!output.status.success());
+    assert!(
+        output.stdout.is_empty(),
+        "stdout: {}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pide hello 1.0"));
+}
+
+/// `be env --shell bash` in `project`, with its own empty LOCALAPPDATA.
+fn env_script(project: &std::path::Path) -> std::process::Output {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.current_dir(project)
+        .env("LOCALAPPDATA", project.join("local"))
+        .env("HOME", project)
+        .args(["env", "--shell", "bash"])
+        .output()
+        .expect("Fallo al ejecutar be")
+}
+
+#[test]
+fn test_foreign_tool_next_to_brisas_toml_only_warns() {
+    let project = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    std::fs::write(project.path().join(".brisas.toml"), "[tools]\n").unwrap();
+    std::fs::write(project.path().join(".tool-versions"), "python 3.11.4\n").unwrap();
+
+    let output = env_script(project.path());
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("deactivate () {"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains(".tool-versions pide python 3.11.4"));
+}
+
+#[test]
+fn test_unsatisfiable_nvmrc_warns() {
+    let project = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    std::fs::write(project.path().join(".nvmrc"), "20\n").unwrap();
+
+    let output = env_script(project.path());
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(".nvmrc pide node 20"));
+}
+
+#[test]
+fn test_clean_keeps_foreign_files_in_custom_cache_dir() {
+    let local = tempfile::tempdir().expect("Fallo al crear directorio temporal");
+    let downloads = local.path().join("Downloads");
+    let key_dir = downloads.join("url-0123456789abcdef");
+    std::fs::create_dir_all(&key_dir).unwrap();
+    std::fs::write(key_dir.join("tool-1.0.zip"), b"zip").unwrap();
+    std::fs::write(downloads.join("tesis.docx"), b"no tocar").unwrap();
+    let index = serde_json::json!({
+        "url-0123456789abcdef": {
+            "key": "url-0123456789abcdef",
+            "tool": "tool",
+            "version": "1.0",
+            "url": "https://example.com/tool-1.0.zip",
+            "file_name": "tool-1.0.zip",
+            "size": 3,
+            "sha256": "x",
+            "fetched_at": "2025-01-01 10:00:00"
+        }
+    });
+    std::fs::write(downloads.join("index.json"), index.to_string()).unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_be"));
+    cmd.env("LOCALAPPDATA", local.path())
+        .env("HOME", local.path())
+        .env("BRISAS_CACHE_DIR", &downloads)
+        .arg("clean")
         .assert()
-        .success() // Deberia salir con 0, pero mostrar error en output
-        .stdout(
-            predicate::str::contains("No se encontro %LOCALAPPDATA%")
-                .or(predicate::str::contains("Hay inconsistencias"))
-                .or(predicate::str::contains("No encontrado")),
-        );
+        .success();
+    assert!(downloads.join("tesis.docx").exists());
+    assert!(!key_dir.exists());
+    assert!(!downloads.join("index.json").exists());
 }